@@ -1,3 +1,7 @@
+/// Crack overlays drawn over a block as it breaks, from barely started to almost broken.
+pub const CRACK_STAGES: u8 = 8;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Atlas {
     Unknown,
     GrassTop,
@@ -14,6 +18,13 @@ pub enum Atlas {
     LogSide,
     LogTop,
     Water,
+    Ice,
+    Slime,
+    SoulSand,
+    Ladder,
+    Vine,
+    Tnt,
+    Crack(u8),
 }
 
 pub fn get_texture_coordinates(origin: Atlas, rotate: u8) -> [[f32; 2]; 4] {
@@ -32,6 +43,13 @@ pub fn get_texture_coordinates(origin: Atlas, rotate: u8) -> [[f32; 2]; 4] {
         Atlas::LogSide => [0.3, 0.2],
         Atlas::LogTop => [0.4, 0.2],
         Atlas::Water => [0.7, 0.0],
+        Atlas::Ice => [0.5, 0.0],
+        Atlas::Slime => [0.6, 0.0],
+        Atlas::SoulSand => [0.5, 0.1],
+        Atlas::Ladder => [0.6, 0.1],
+        Atlas::Vine => [0.7, 0.1],
+        Atlas::Tnt => [0.5, 0.2],
+        Atlas::Crack(stage) => [stage.min(CRACK_STAGES - 1) as f32 * 0.1, 0.3],
         _ => [0.9, 0.9],
     };
     let mut ret = [[x, y], [x + 0.1, y], [x + 0.1, y + 0.1], [x, y + 0.1]];
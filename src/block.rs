@@ -1,11 +1,196 @@
-use crate::atlas::Atlas;
+use cgmath::Vector3;
 
-#[derive(Debug, Copy, Clone)]
+use crate::{atlas::Atlas, item::Tool};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Block {
     pub block_id: u8,
     pub block_state: u8,
 }
 
+impl Block {
+    pub fn properties(&self) -> BlockProperties {
+        get_properties(self.block_id)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BlockProperties {
+    /// Blocks movement of the player.
+    pub solid: bool,
+    /// Placing a block here overwrites it.
+    pub replaceable: bool,
+    /// Stops the crosshair ray so it can be broken or placed against.
+    pub targetable: bool,
+    pub fluid: bool,
+    /// Touching it lets the player climb up and down.
+    pub climbable: bool,
+    pub model: Model,
+    /// How the block feels to stand on.
+    pub material: Material,
+    /// Seconds it takes to break by hand, zero breaks at once.
+    pub hardness: f32,
+    /// Breaks faster with this tool.
+    pub tool: Option<Tool>,
+    /// Explosions have to reach the block with more strength than this to remove it.
+    pub blast_resistance: f32,
+    /// Power of the explosion it sets off when caught in one.
+    pub explosive: Option<f32>,
+}
+
+/// How many times faster the right tool breaks a block.
+const TOOL_SPEED: f32 = 4.0;
+
+/// Shape the block is meshed as.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Model {
+    Cube,
+    /// A thin panel against the wall given by `wall_direction` of the block state.
+    Wall,
+}
+
+impl BlockProperties {
+    const SOLID: Self = Self {
+        solid: true,
+        replaceable: false,
+        targetable: true,
+        fluid: false,
+        climbable: false,
+        model: Model::Cube,
+        material: Material::DEFAULT,
+        hardness: 1.0,
+        tool: None,
+        blast_resistance: 1.0,
+        explosive: None,
+    };
+    const FLUID: Self = Self {
+        solid: false,
+        replaceable: true,
+        targetable: false,
+        fluid: true,
+        climbable: false,
+        model: Model::Cube,
+        material: Material::DEFAULT,
+        hardness: 1.0,
+        tool: None,
+        blast_resistance: 100.0,
+        explosive: None,
+    };
+    const CLIMBABLE: Self = Self {
+        solid: false,
+        replaceable: false,
+        targetable: true,
+        fluid: false,
+        climbable: true,
+        model: Model::Wall,
+        material: Material::DEFAULT,
+        hardness: 1.0,
+        tool: None,
+        blast_resistance: 0.5,
+        explosive: None,
+    };
+
+    const fn with_material(self, material: Material) -> Self {
+        Self { material, ..self }
+    }
+
+    const fn mined(self, hardness: f32, tool: Option<Tool>) -> Self {
+        Self {
+            hardness,
+            tool,
+            ..self
+        }
+    }
+
+    const fn resists(self, blast_resistance: f32) -> Self {
+        Self {
+            blast_resistance,
+            ..self
+        }
+    }
+
+    const fn explodes(self, power: f32) -> Self {
+        Self {
+            explosive: Some(power),
+            ..self
+        }
+    }
+
+    /// Seconds to break the block holding `tool`, `None` being the bare hand.
+    pub fn break_time(&self, tool: Option<Tool>) -> f32 {
+        if tool.is_some() && tool == self.tool {
+            self.hardness / TOOL_SPEED
+        } else {
+            self.hardness
+        }
+    }
+}
+
+/// Physics of a block's surface.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Material {
+    /// Share of horizontal velocity kept each tick while standing on the block.
+    pub friction: f32,
+    /// Share of the landing speed bounced back up.
+    pub restitution: f32,
+    /// Multiplies walking speed while standing on the block.
+    pub speed_factor: f32,
+}
+
+impl Material {
+    pub const DEFAULT: Self = Self {
+        friction: 0.7,
+        restitution: 0.0,
+        speed_factor: 1.0,
+    };
+    const ICE: Self = Self {
+        friction: 0.95,
+        ..Self::DEFAULT
+    };
+    const SLIME: Self = Self {
+        restitution: 0.8,
+        ..Self::DEFAULT
+    };
+    const SOUL_SAND: Self = Self {
+        speed_factor: 0.4,
+        ..Self::DEFAULT
+    };
+}
+
+/// Block ids below this one are defined, anything higher falls back to a plain solid block.
+pub const BLOCK_COUNT: u8 = 18;
+
+pub fn get_properties(block_id: u8) -> BlockProperties {
+    use BlockProperties as P;
+    use Tool::*;
+    match block_id {
+        0 => P::FLUID,
+        1 => P::SOLID.mined(0.9, Some(Shovel)).resists(0.6),
+        2 | 4 => P::SOLID.mined(0.75, Some(Shovel)).resists(0.5),
+        3 | 5 | 6 => P::SOLID.mined(7.5, Some(Pickaxe)).resists(3.0),
+        7 => P::SOLID.mined(10.0, Some(Pickaxe)).resists(6.0),
+        8 | 10 => P::SOLID.mined(3.0, Some(Axe)).resists(1.5),
+        9 => P::SOLID.mined(0.45, None).resists(0.3),
+        11 => P::SOLID.mined(0.3, None).resists(0.2),
+        12 => P::SOLID
+            .with_material(Material::ICE)
+            .mined(0.75, Some(Pickaxe))
+            .resists(0.5),
+        13 => P::SOLID
+            .with_material(Material::SLIME)
+            .mined(0.0, None)
+            .resists(0.0),
+        14 => P::SOLID
+            .with_material(Material::SOUL_SAND)
+            .mined(0.75, Some(Shovel))
+            .resists(0.5),
+        15 => P::CLIMBABLE.mined(0.6, Some(Axe)),
+        16 => P::CLIMBABLE.mined(0.3, None).resists(0.2),
+        17 => P::SOLID.mined(0.0, None).resists(0.0).explodes(4.0),
+        _ => P::SOLID,
+    }
+}
+
 pub fn get_texture(block_id: u8, normal: [i8; 3]) -> Atlas {
     enum Face {
         Top,
@@ -42,7 +227,35 @@ pub fn get_texture(block_id: u8, normal: [i8; 3]) -> Atlas {
             Face::Bottom => Atlas::LogTop,
             _ => Atlas::LogSide,
         },
+        11 => Atlas::Leaves,
+        12 => Atlas::Ice,
+        13 => Atlas::Slime,
+        14 => Atlas::SoulSand,
+        15 => Atlas::Ladder,
+        16 => Atlas::Vine,
+        17 => Atlas::Tnt,
         0 => Atlas::Water,
         _ => Atlas::Unknown,
     }
 }
+
+/// Blocks that let light and the faces behind them through.
+pub fn is_transparent(block_id: u8) -> bool {
+    matches!(block_id, 0 | 7 | 9 | 15 | 16)
+}
+
+/// Direction from a `Model::Wall` block to the wall it hangs on.
+pub fn wall_direction(block_state: u8) -> Vector3<i32> {
+    match block_state % 4 {
+        0 => Vector3::unit_x(),
+        1 => -Vector3::unit_x(),
+        2 => Vector3::unit_z(),
+        _ => -Vector3::unit_z(),
+    }
+}
+
+/// Block state of a `Model::Wall` block hanging on the wall in `direction`, `None` for
+/// anything but the four horizontal directions.
+pub fn wall_state(direction: Vector3<i32>) -> Option<u8> {
+    (0..4).find(|&state| wall_direction(state) == direction)
+}
@@ -0,0 +1,315 @@
+use anyhow::{anyhow, bail, Result};
+
+use crate::{
+    item,
+    player::inventory::{ItemStack, MAX_STACK_SIZE},
+};
+
+/// Width and height of the crafting grid.
+pub const GRID_WIDTH: usize = 3;
+
+/// The recipes shipped with the game, see the file for the format.
+const RECIPES: &str = include_str!("../assets/recipes.txt");
+
+/// Items laid out for crafting, at most one per cell.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct CraftingGrid {
+    cells: [[Option<u8>; GRID_WIDTH]; GRID_WIDTH],
+}
+
+impl CraftingGrid {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Cells are counted from the top left.
+    pub fn set(&mut self, x: usize, y: usize, item: Option<u8>) {
+        self.cells[y][x] = item;
+    }
+
+    /// Every item on the grid, row by row.
+    pub fn items(&self) -> impl Iterator<Item = u8> + '_ {
+        self.cells.iter().flatten().flatten().copied()
+    }
+
+    /// The same layout moved into the top left corner, so shapes compare equal wherever they
+    /// sit on the grid.
+    fn aligned(&self) -> Self {
+        let filled = |x: usize, y: usize| self.cells[y][x].is_some();
+        let left = (0..GRID_WIDTH)
+            .find(|&x| (0..GRID_WIDTH).any(|y| filled(x, y)))
+            .unwrap_or(0);
+        let top = (0..GRID_WIDTH)
+            .find(|&y| (0..GRID_WIDTH).any(|x| filled(x, y)))
+            .unwrap_or(0);
+
+        let mut aligned = Self::empty();
+        for y in top..GRID_WIDTH {
+            for x in left..GRID_WIDTH {
+                aligned.cells[y - top][x - left] = self.cells[y][x];
+            }
+        }
+        aligned
+    }
+}
+
+/// What has to be on the grid for a recipe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// Items laid out in this shape, anywhere on the grid.
+    Shaped(CraftingGrid),
+    /// These items in any cells, kept sorted.
+    Shapeless(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipe {
+    pub pattern: Pattern,
+    pub result: ItemStack,
+}
+
+impl Recipe {
+    /// Whether the grid holds exactly what the recipe asks for and nothing else.
+    pub fn matches(&self, grid: &CraftingGrid) -> bool {
+        match &self.pattern {
+            Pattern::Shaped(shape) => shape.aligned() == grid.aligned(),
+            Pattern::Shapeless(items) => {
+                let mut on_grid: Vec<u8> = grid.items().collect();
+                on_grid.sort_unstable();
+                on_grid == *items
+            }
+        }
+    }
+
+    /// Parses one line of a recipes file, like `shapeless 10 = 8 x4`.
+    fn parse(line: &str) -> Result<Self> {
+        let (pattern, result) = line
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected `shaped|shapeless ingredients = result`"))?;
+        let (kind, ingredients) = pattern
+            .trim()
+            .split_once(char::is_whitespace)
+            .unwrap_or((pattern.trim(), ""));
+        let pattern = match kind {
+            "shaped" => Pattern::Shaped(parse_shape(ingredients)?),
+            "shapeless" => {
+                let mut items = ingredients
+                    .split_whitespace()
+                    .map(parse_item)
+                    .collect::<Result<Vec<_>>>()?;
+                if items.is_empty() || items.len() > GRID_WIDTH * GRID_WIDTH {
+                    bail!(
+                        "expected 1 to {} ingredients, got {}",
+                        GRID_WIDTH * GRID_WIDTH,
+                        items.len()
+                    );
+                }
+                items.sort_unstable();
+                Pattern::Shapeless(items)
+            }
+            kind => bail!("unknown recipe kind {kind}, expected shaped or shapeless"),
+        };
+        Ok(Self {
+            pattern,
+            result: parse_result(result)?,
+        })
+    }
+}
+
+/// Reads an item id, which has to be a defined block or tool.
+fn parse_item(text: &str) -> Result<u8> {
+    let item = text
+        .parse()
+        .map_err(|_| anyhow!("expected an item id, got {text}"))?;
+    if !item::is_known(item) {
+        bail!("unknown item {item}");
+    }
+    Ok(item)
+}
+
+/// Reads rows separated by `/`, with `.` for empty cells.
+fn parse_shape(text: &str) -> Result<CraftingGrid> {
+    let rows: Vec<Vec<&str>> = text
+        .split('/')
+        .map(|row| row.split_whitespace().collect())
+        .collect();
+    if rows.len() > GRID_WIDTH || rows.iter().any(|row| row.len() > GRID_WIDTH) {
+        bail!("shape is larger than {GRID_WIDTH}x{GRID_WIDTH}");
+    }
+    if rows.iter().any(|row| row.len() != rows[0].len()) {
+        bail!("shape rows differ in width");
+    }
+
+    let mut shape = CraftingGrid::empty();
+    for (y, row) in rows.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if *cell != "." {
+                shape.set(x, y, Some(parse_item(cell)?));
+            }
+        }
+    }
+    if shape.items().next().is_none() {
+        bail!("shape has no ingredients");
+    }
+    Ok(shape)
+}
+
+/// Reads an item id and an optional count like `x4`.
+fn parse_result(text: &str) -> Result<ItemStack> {
+    let mut parts = text.split_whitespace();
+    let item = parse_item(parts.next().ok_or_else(|| anyhow!("missing result"))?)?;
+    let count = match parts.next() {
+        None => 1,
+        Some(text) => text
+            .strip_prefix('x')
+            .and_then(|count| count.parse().ok())
+            .filter(|count| (1..=MAX_STACK_SIZE).contains(count))
+            .ok_or_else(|| anyhow!("expected a count from x1 to x{MAX_STACK_SIZE}, got {text}"))?,
+    };
+    if let Some(extra) = parts.next() {
+        bail!("unexpected {extra} after the result");
+    }
+    Ok(ItemStack { item, count })
+}
+
+/// Every recipe that can be crafted, tried in the order they were read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipes {
+    recipes: Vec<Recipe>,
+}
+
+impl Recipes {
+    /// Reads a recipes file, `#` starts a comment. Errors include the line number.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut recipes = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let recipe = Recipe::parse(line).map_err(|e| anyhow!("line {}: {e}", number + 1))?;
+            recipes.push(recipe);
+        }
+        Ok(Self { recipes })
+    }
+
+    /// The first recipe the grid matches.
+    pub fn find(&self, grid: &CraftingGrid) -> Option<&Recipe> {
+        self.recipes.iter().find(|recipe| recipe.matches(grid))
+    }
+}
+
+impl Default for Recipes {
+    fn default() -> Self {
+        Self::parse(RECIPES).expect("built-in recipes are invalid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[[Option<u8>; GRID_WIDTH]; GRID_WIDTH]) -> CraftingGrid {
+        CraftingGrid { cells: *rows }
+    }
+
+    #[test]
+    fn built_in_recipes_parse() {
+        let recipes = Recipes::default();
+
+        assert!(!recipes.recipes.is_empty());
+    }
+
+    #[test]
+    fn a_log_makes_planks() {
+        let recipes = Recipes::default();
+        let mut grid = CraftingGrid::empty();
+        grid.set(1, 2, Some(10));
+
+        let recipe = recipes.find(&grid).unwrap();
+
+        assert_eq!(recipe.result, ItemStack { item: 8, count: 4 });
+    }
+
+    #[test]
+    fn shapes_match_anywhere_on_the_grid() {
+        let recipes = Recipes::parse("shaped 3 3 / 3 3 = 5 x4").unwrap();
+        let s = Some(3);
+
+        assert!(recipes
+            .find(&grid(&[[s, s, None], [s, s, None], [None; 3]]))
+            .is_some());
+        assert!(recipes
+            .find(&grid(&[[None; 3], [None, s, s], [None, s, s]]))
+            .is_some());
+        assert!(recipes
+            .find(&grid(&[[s, s, None], [None, s, s], [None; 3]]))
+            .is_none());
+        assert!(recipes
+            .find(&grid(&[[s, s, s], [s, s, None], [None; 3]]))
+            .is_none());
+    }
+
+    #[test]
+    fn shapes_keep_their_gaps() {
+        let recipes = Recipes::parse("shaped 8 . 8 / 8 8 8 / 8 . 8 = 15 x3").unwrap();
+        let p = Some(8);
+
+        assert!(recipes
+            .find(&grid(&[[p, None, p], [p, p, p], [p, None, p]]))
+            .is_some());
+        assert!(recipes
+            .find(&grid(&[[p, p, p], [p, p, p], [p, None, p]]))
+            .is_none());
+    }
+
+    #[test]
+    fn shapeless_ignores_order_and_position() {
+        let recipes = Recipes::parse("shapeless 5 16 = 6").unwrap();
+
+        let mut grid = CraftingGrid::empty();
+        grid.set(2, 0, Some(16));
+        grid.set(0, 2, Some(5));
+        assert_eq!(
+            recipes.find(&grid).unwrap().result,
+            ItemStack { item: 6, count: 1 }
+        );
+
+        grid.set(1, 1, Some(5));
+        assert!(recipes.find(&grid).is_none());
+    }
+
+    #[test]
+    fn empty_grids_match_nothing() {
+        assert!(Recipes::default().find(&CraftingGrid::empty()).is_none());
+    }
+
+    #[test]
+    fn unknown_items_are_rejected() {
+        let error = Recipes::parse("# planks\nshapeless 10 = 8\nshapeless 99 = 8")
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("line 3"), "{error}");
+        assert!(error.contains("unknown item 99"), "{error}");
+        assert!(Recipes::parse("shapeless 10 = 200").is_err());
+    }
+
+    #[test]
+    fn malformed_recipes_are_rejected() {
+        for line in [
+            "shapeless 10",
+            "mixed 10 = 8",
+            "shapeless = 8",
+            "shaped . / . = 8",
+            "shaped 3 3 / 3 = 5",
+            "shaped 3 3 3 3 = 5",
+            "shapeless 10 = 8 x0",
+            "shapeless 10 = 8 x65",
+            "shapeless 10 = 8 x4 x4",
+            "shapeless log = 8",
+        ] {
+            assert!(Recipes::parse(line).is_err(), "{line}");
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use cgmath::{InnerSpace, Point3, Rad, Vector3};
+
+use super::{Body, Entities, Entity, EntityId, EntityKind, RenderModel, Transform, Velocity};
+use crate::{
+    player::{
+        inventory::{ItemStack, MAX_STACK_SIZE},
+        Player,
+    },
+    terrain::{BlockPos, World},
+};
+
+/// Width and height of a dropped item.
+pub const DROP_SIZE: f32 = 0.25;
+/// Upward speed a drop pops out of a broken block with.
+const POP_SPEED: f32 = 5.0;
+/// Share of horizontal velocity kept each tick on the ground.
+const GROUND_FRICTION: f32 = 0.6;
+/// Drops of the same item with centers closer than this become one stack.
+const MERGE_RADIUS: f32 = 0.5;
+/// How far past the player's box drops are picked up, per axis.
+const PICKUP_REACH: Vector3<f32> = Vector3::new(1.0, 0.5, 1.0);
+/// Ticks before a new drop can be picked up, so it is seen popping out first.
+const PICKUP_DELAY: u32 = 10;
+/// Ticks a drop lies around before it disappears, five minutes.
+pub const DESPAWN_TICKS: u32 = 6000;
+/// Radians a drop turns per second.
+const SPIN_SPEED: f32 = 1.4;
+
+/// An item stack lying on the ground with its feet at `position`, popping up.
+pub fn new(position: Point3<f32>, stack: ItemStack) -> Entity {
+    Entity {
+        transform: Transform::new(position, Rad(0.0)),
+        velocity: Some(Velocity {
+            linear: Vector3::new(0.0, POP_SPEED, 0.0),
+            spin: SPIN_SPEED,
+            gravity: true,
+            friction: GROUND_FRICTION,
+        }),
+        body: Some(Body {
+            width: DROP_SIZE,
+            height: DROP_SIZE,
+        }),
+        model: Some(RenderModel::Cube {
+            item: stack.item,
+            size: DROP_SIZE,
+        }),
+        kind: EntityKind::Item(stack),
+        age: 0,
+        on_ground: false,
+    }
+}
+
+/// The stack an item entity holds, None for other kinds.
+fn stack(entity: &Entity) -> Option<ItemStack> {
+    match entity.kind {
+        EntityKind::Item(stack) => Some(stack),
+        _ => None,
+    }
+}
+
+fn center(entity: &Entity) -> Point3<f32> {
+    entity.transform.position + Vector3::new(0.0, DROP_SIZE / 2.0, 0.0)
+}
+
+impl World {
+    /// Drops `stack` in the middle of the block at `position`, popping up out of it.
+    pub fn drop_item(&mut self, position: BlockPos, stack: ItemStack) -> EntityId {
+        let feet = position.bottom_center() + Vector3::new(0.0, (1.0 - DROP_SIZE) / 2.0, 0.0);
+        self.entities.spawn(new(feet, stack))
+    }
+
+    /// Moves dropped items within reach of the player into its inventory. Whatever does not
+    /// fit stays on the ground.
+    pub fn pick_up_items(&mut self, player: &mut Player) {
+        if !player.mode.can_interact() || player.health.is_dead() {
+            return;
+        }
+        let reach = player.aabb().grow(PICKUP_REACH);
+        let in_reach: Vec<(EntityId, ItemStack)> = self
+            .entities
+            .iter()
+            .filter(|(_, entity)| entity.age >= PICKUP_DELAY && reach.intersects(&entity.aabb()))
+            .filter_map(|(id, entity)| Some((id, stack(entity)?)))
+            .collect();
+        for (id, stack) in in_reach {
+            let left = player.inventory.add(stack.item, stack.count);
+            if left == 0 {
+                self.entities.remove(id);
+            } else if let Some(entity) = self.entities.get_mut(id) {
+                entity.kind = EntityKind::Item(ItemStack {
+                    count: left,
+                    ..stack
+                });
+            }
+        }
+    }
+}
+
+/// Adds later drops into earlier ones of the same item nearby, as long as the stack fits. The
+/// merged drop counts as new again.
+pub(super) fn merge(entities: &mut Entities) {
+    let items: Vec<EntityId> = entities
+        .iter()
+        .filter(|(_, entity)| stack(entity).is_some())
+        .map(|(id, _)| id)
+        .collect();
+    for (i, &into) in items.iter().enumerate() {
+        for &from in &items[i + 1..] {
+            let (Some(a), Some(b)) = (entities.get(into), entities.get(from)) else {
+                continue;
+            };
+            let (Some(a_stack), Some(b_stack)) = (stack(a), stack(b)) else {
+                continue;
+            };
+            let total = a_stack.count as u32 + b_stack.count as u32;
+            if a_stack.item != b_stack.item
+                || total > MAX_STACK_SIZE as u32
+                || (center(a) - center(b)).magnitude() >= MERGE_RADIUS
+            {
+                continue;
+            }
+            let age = a.age.min(b.age);
+            entities.remove(from);
+            if let Some(entity) = entities.get_mut(into) {
+                entity.kind = EntityKind::Item(ItemStack {
+                    item: a_stack.item,
+                    count: total as u8,
+                });
+                entity.age = age;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::{inventory::Inventory, movement::MovementMode};
+
+    fn stack(item: u8, count: u8) -> ItemStack {
+        ItemStack { item, count }
+    }
+
+    fn tick(world: &mut World, ticks: u32) {
+        for _ in 0..ticks {
+            world.tick_entities();
+        }
+    }
+
+    fn stacks(world: &World) -> Vec<ItemStack> {
+        world
+            .entities
+            .iter()
+            .filter_map(|(_, entity)| super::stack(entity))
+            .collect()
+    }
+
+    #[test]
+    fn drops_fall_onto_the_ground() {
+        let mut world = World::with_blocks(&[([0, 0, 0], 3)]);
+        let id = world.drop_item([0, 4, 0].into(), stack(2, 1));
+
+        tick(&mut world, 40);
+
+        let position = world.entities.get(id).unwrap().transform.position;
+        assert!((position.y - 1.0).abs() < 1e-3, "{position:?}");
+        assert_eq!(position.x, 0.5);
+        assert_eq!(position.z, 0.5);
+    }
+
+    #[test]
+    fn nearby_drops_of_the_same_item_merge() {
+        let mut world = World::with_blocks(&[([0, 0, 0], 3), ([1, 0, 0], 3)]);
+        world.drop_item([0, 1, 0].into(), stack(2, 1));
+        world.drop_item([0, 1, 0].into(), stack(2, 3));
+        world.drop_item([0, 1, 0].into(), stack(4, 1));
+        world.drop_item([1, 1, 0].into(), stack(2, 1));
+
+        tick(&mut world, 1);
+
+        assert_eq!(stacks(&world), vec![stack(2, 4), stack(4, 1), stack(2, 1)]);
+    }
+
+    #[test]
+    fn full_stacks_do_not_merge() {
+        let mut world = World::with_blocks(&[([0, 0, 0], 3)]);
+        world.drop_item([0, 1, 0].into(), stack(2, MAX_STACK_SIZE));
+        world.drop_item([0, 1, 0].into(), stack(2, 1));
+
+        tick(&mut world, 1);
+
+        assert_eq!(world.entities.len(), 2);
+    }
+
+    #[test]
+    fn drops_despawn_after_a_while() {
+        let mut world = World::with_blocks(&[([0, 0, 0], 3)]);
+        world.drop_item([0, 1, 0].into(), stack(2, 1));
+
+        tick(&mut world, DESPAWN_TICKS - 1);
+        assert_eq!(world.entities.len(), 1);
+        tick(&mut world, 1);
+        assert!(world.entities.is_empty());
+    }
+
+    #[test]
+    fn players_pick_up_drops_within_reach() {
+        let mut world = World::with_blocks(&[([0, 0, 0], 3), ([4, 0, 0], 3)]);
+        world.drop_item([0, 1, 0].into(), stack(2, 5));
+        world.drop_item([4, 1, 0].into(), stack(4, 5));
+        let mut player = Player::new([1.5, 1.0, 0.5].into());
+        player.inventory = Inventory::empty();
+
+        world.pick_up_items(&mut player);
+        assert_eq!(world.entities.len(), 2, "picked up before the delay");
+
+        tick(&mut world, PICKUP_DELAY);
+        world.pick_up_items(&mut player);
+
+        assert_eq!(player.inventory.selected_stack(), Some(stack(2, 5)));
+        assert_eq!(stacks(&world), vec![stack(4, 5)]);
+    }
+
+    #[test]
+    fn spectators_pick_up_nothing() {
+        let mut world = World::with_blocks(&[([0, 0, 0], 3)]);
+        world.drop_item([0, 1, 0].into(), stack(2, 5));
+        let mut player = Player::new([0.5, 1.0, 0.5].into());
+        player.mode = MovementMode::Spectator;
+
+        tick(&mut world, PICKUP_DELAY);
+        world.pick_up_items(&mut player);
+
+        assert_eq!(world.entities.len(), 1);
+    }
+}
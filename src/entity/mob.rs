@@ -0,0 +1,438 @@
+use std::mem;
+
+use cgmath::{InnerSpace, Point3, Rad, Vector3};
+
+use super::{
+    pathfind::{Search, SearchStatus, Walker},
+    Body, Entity, EntityId, EntityKind, RenderModel, Transform, Velocity,
+};
+use crate::{
+    player::Player,
+    terrain::{tick::TickRng, BlockPos, World},
+};
+
+/// Width and height of a mob.
+pub const MOB_SIZE: f32 = 0.8;
+/// How mobs get around: one block tall, falling at most three and happy to swim.
+const WALKER: Walker = Walker {
+    height: 1,
+    max_drop: 3,
+    swims: true,
+    max_nodes: 1024,
+};
+/// Path search nodes each mob may look at per tick, longer searches carry on next tick.
+const NODES_PER_TICK: usize = 64;
+/// Horizontal speeds in blocks per second.
+const WANDER_SPEED: f32 = 2.0;
+const FOLLOW_SPEED: f32 = 3.5;
+const FLEE_SPEED: f32 = 4.5;
+/// Upward speed of a jump, enough to get up one block.
+const JUMP_SPEED: f32 = 9.0;
+/// Upward speed while swimming.
+const SWIM_SPEED: f32 = 2.0;
+/// Share of horizontal velocity kept each tick on the ground.
+const GROUND_FRICTION: f32 = 0.6;
+/// Tame mobs follow a player within this range and stop once this close.
+const FOLLOW_RANGE: f32 = 16.0;
+const FOLLOW_DISTANCE: f32 = 2.5;
+/// Timid mobs run once the player is this close, about this many blocks away.
+const FLEE_RANGE: f32 = 6.0;
+const FLEE_DISTANCE: f32 = 8.0;
+/// Wander targets are at most this many blocks away on either axis.
+const WANDER_RADIUS: i32 = 6;
+/// One in this many idle ticks a wandering mob sets off somewhere.
+const WANDER_CHANCE: u64 = 80;
+/// Targets are moved up or down by at most this much to find somewhere to stand.
+const TARGET_SEARCH_HEIGHT: i32 = 4;
+/// Ticks between searches for a new path to a player that keeps moving.
+const REPATH_TICKS: u32 = 20;
+/// Waypoints count as reached this close horizontally.
+const WAYPOINT_RADIUS: f32 = 0.25;
+/// Ticks of not moving while on a path before giving up on it.
+const STUCK_TICKS: u32 = 40;
+
+/// How a mob feels about the player.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Temper {
+    /// Follows the player around.
+    Tame,
+    /// Runs from the player.
+    Timid,
+}
+
+impl Temper {
+    pub fn id(self) -> u8 {
+        match self {
+            Self::Tame => 0,
+            Self::Timid => 1,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::Tame),
+            1 => Some(Self::Timid),
+            _ => None,
+        }
+    }
+
+    /// Block the mob looks like.
+    fn texture(self) -> u8 {
+        match self {
+            Self::Tame => 13,
+            Self::Timid => 4,
+        }
+    }
+}
+
+/// What a mob is up to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Activity {
+    Wander,
+    Follow,
+    Flee,
+}
+
+/// The mind of a mob, it moves through the entity's velocity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mob {
+    pub temper: Temper,
+    pub activity: Activity,
+    /// A path being looked for, possibly over several ticks.
+    search: Option<Box<Search>>,
+    /// Feet blocks left to walk through.
+    path: Vec<BlockPos>,
+    /// Ticks since the last search started.
+    since_search: u32,
+    /// Ticks spent on a path without moving.
+    stuck: u32,
+    rng: TickRng,
+}
+
+impl Mob {
+    fn new(temper: Temper, seed: u64) -> Self {
+        Self {
+            temper,
+            activity: Activity::Wander,
+            search: None,
+            path: Vec::new(),
+            since_search: 0,
+            stuck: 0,
+            rng: TickRng::new(seed),
+        }
+    }
+
+    /// Seed of the random choices still to come, saved so they carry on after loading.
+    pub fn rng_state(&self) -> u64 {
+        self.rng.state()
+    }
+
+    fn idle(&self) -> bool {
+        self.path.is_empty() && self.search.is_none()
+    }
+
+    fn stop(&mut self) {
+        self.path.clear();
+        self.search = None;
+        self.stuck = 0;
+    }
+
+    fn start_search(&mut self, from: BlockPos, to: BlockPos) {
+        self.search = Some(Box::new(Search::new(WALKER, from, to)));
+        self.since_search = 0;
+    }
+
+    /// Picks what to do about the player and where to go for it.
+    fn think(
+        &mut self,
+        world: &World,
+        feet: BlockPos,
+        position: Point3<f32>,
+        player: Option<Point3<f32>>,
+    ) {
+        let distance = player.map(|player| (player - position).magnitude());
+        let activity = match (self.temper, distance) {
+            (Temper::Tame, Some(d)) if d < FOLLOW_RANGE => Activity::Follow,
+            (Temper::Timid, Some(d)) if d < FLEE_RANGE => Activity::Flee,
+            _ => Activity::Wander,
+        };
+        if activity != self.activity {
+            self.activity = activity;
+            self.stop();
+        }
+        self.since_search += 1;
+
+        let target = match (activity, player) {
+            (Activity::Follow, Some(player)) => {
+                if distance.is_some_and(|d| d <= FOLLOW_DISTANCE) {
+                    self.stop();
+                    return;
+                }
+                if !self.idle() && self.since_search < REPATH_TICKS {
+                    return;
+                }
+                BlockPos::containing(player)
+            }
+            (Activity::Flee, Some(player)) => {
+                if !self.idle() {
+                    return;
+                }
+                let away = Vector3::new(position.x - player.x, 0.0, position.z - player.z);
+                let away = if away.magnitude() > 0.0 {
+                    away.normalize() * FLEE_DISTANCE
+                } else {
+                    Vector3::new(FLEE_DISTANCE, 0.0, 0.0)
+                };
+                BlockPos::containing(position + away)
+            }
+            _ => {
+                if !self.idle() || self.rng.below(WANDER_CHANCE) != 0 {
+                    return;
+                }
+                let mut offset =
+                    || self.rng.below(2 * WANDER_RADIUS as u64 + 1) as i32 - WANDER_RADIUS;
+                feet + Vector3::new(offset(), 0, offset())
+            }
+        };
+        if let Some(goal) = WALKER.stand_near(world, target, TARGET_SEARCH_HEIGHT) {
+            if goal != feet {
+                self.start_search(feet, goal);
+            }
+        }
+    }
+
+    fn speed(&self) -> f32 {
+        match self.activity {
+            Activity::Wander => WANDER_SPEED,
+            Activity::Follow => FOLLOW_SPEED,
+            Activity::Flee => FLEE_SPEED,
+        }
+    }
+}
+
+/// A mob standing with its feet at `position`.
+pub fn new(position: Point3<f32>, temper: Temper, seed: u64) -> Entity {
+    Entity {
+        transform: Transform::new(position, Rad(0.0)),
+        velocity: Some(Velocity {
+            linear: Vector3::new(0.0, 0.0, 0.0),
+            spin: 0.0,
+            gravity: true,
+            friction: GROUND_FRICTION,
+        }),
+        body: Some(Body {
+            width: MOB_SIZE,
+            height: MOB_SIZE,
+        }),
+        model: Some(RenderModel::Cube {
+            item: temper.texture(),
+            size: MOB_SIZE,
+        }),
+        kind: EntityKind::Mob(Mob::new(temper, seed)),
+        age: 0,
+        on_ground: false,
+    }
+}
+
+/// Thinks and steers one mob for the coming tick.
+fn update(entity: &mut Entity, world: &World, player: Option<Point3<f32>>) {
+    let EntityKind::Mob(mob) = &mut entity.kind else {
+        return;
+    };
+    let Some(velocity) = &mut entity.velocity else {
+        return;
+    };
+    let transform = &mut entity.transform;
+    let position = transform.position;
+    let feet = BlockPos::containing(position);
+
+    mob.think(world, feet, position, player);
+    if let Some(search) = &mut mob.search {
+        match search.step(world, NODES_PER_TICK) {
+            SearchStatus::Searching => {}
+            SearchStatus::Found(path) => {
+                mob.path = path;
+                mob.search = None;
+                mob.stuck = 0;
+            }
+            SearchStatus::Failed => mob.search = None,
+        }
+    }
+
+    let horizontal = |to: Point3<f32>| Vector3::new(to.x - position.x, 0.0, to.z - position.z);
+    while let Some(&next) = mob.path.first() {
+        if next == feet && horizontal(next.bottom_center()).magnitude() < WAYPOINT_RADIUS {
+            mob.path.remove(0);
+        } else {
+            break;
+        }
+    }
+
+    let swimming = world.is_fluid(feet);
+    let Some(&next) = mob.path.first() else {
+        velocity.linear.x = 0.0;
+        velocity.linear.z = 0.0;
+        if swimming {
+            velocity.linear.y = SWIM_SPEED;
+        }
+        return;
+    };
+
+    let to = horizontal(next.bottom_center());
+    if to.magnitude() > WAYPOINT_RADIUS / 2.0 {
+        let motion = to.normalize() * mob.speed();
+        velocity.linear.x = motion.x;
+        velocity.linear.z = motion.z;
+        transform.yaw = Rad(to.z.atan2(to.x));
+    } else {
+        velocity.linear.x = 0.0;
+        velocity.linear.z = 0.0;
+    }
+    if swimming && next.y >= feet.y {
+        velocity.linear.y = SWIM_SPEED;
+    } else if entity.on_ground && next.y > feet.y {
+        velocity.linear.y = JUMP_SPEED;
+    }
+
+    let moved = horizontal(transform.previous_position).magnitude();
+    mob.stuck = if moved < 0.01 { mob.stuck + 1 } else { 0 };
+    if mob.stuck > STUCK_TICKS {
+        mob.stop();
+    }
+}
+
+impl World {
+    /// Adds a mob standing in the block at `position`.
+    pub fn spawn_mob(&mut self, position: BlockPos, temper: Temper) -> EntityId {
+        let seed = self.game_tick ^ ((self.entities.next_id() + 1) << 32);
+        self.entities
+            .spawn(new(position.bottom_center(), temper, seed))
+    }
+
+    /// Adds one mob of each temper a few blocks from `center`, where there is room to stand.
+    pub fn spawn_mobs_around(&mut self, center: BlockPos) {
+        for (offset, temper) in [
+            (Vector3::new(3, 0, 0), Temper::Tame),
+            (Vector3::new(-3, 0, 2), Temper::Timid),
+        ] {
+            if let Some(feet) = WALKER.stand_near(self, center + offset, TARGET_SEARCH_HEIGHT) {
+                self.spawn_mob(feet, temper);
+            }
+        }
+    }
+
+    /// Whether any mob exists, loaded or not.
+    pub fn has_mobs(&self) -> bool {
+        self.entities
+            .iter()
+            .any(|(_, entity)| matches!(entity.kind, EntityKind::Mob(_)))
+    }
+
+    /// Lets every mob in a loaded chunk decide where to go next and set off. Players that
+    /// cannot interact with the world are ignored.
+    pub fn update_mobs(&mut self, player: &Player) {
+        let player =
+            (player.mode.can_interact() && !player.health.is_dead()).then_some(player.position);
+        let mut entities = mem::take(&mut self.entities);
+        for entity in entities.entities.values_mut() {
+            if self.chunks.contains_key(&entity.chunk()) {
+                update(entity, self, player);
+            }
+        }
+        self.entities = entities;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{block::Block, player::movement::MovementMode};
+
+    /// A stone floor at y = 0 across the loaded chunks, with `blocks` on top.
+    fn flat_world(blocks: &[([i32; 3], u8)]) -> World {
+        let mut world = World::with_blocks(&[]);
+        for x in -16..32 {
+            for z in -16..32 {
+                world.set_block(
+                    [x, 0, z].into(),
+                    Some(Block {
+                        block_id: 3,
+                        block_state: 0,
+                    }),
+                );
+            }
+        }
+        for (position, block_id) in blocks {
+            world.set_block(
+                (*position).into(),
+                Some(Block {
+                    block_id: *block_id,
+                    block_state: 0,
+                }),
+            );
+        }
+        world
+    }
+
+    fn run(world: &mut World, player: &Player, ticks: u32) {
+        for _ in 0..ticks {
+            world.tick_entities();
+            world.update_mobs(player);
+        }
+    }
+
+    fn position(world: &World, id: EntityId) -> Point3<f32> {
+        world.entities.get(id).unwrap().transform.position
+    }
+
+    fn activity(world: &World, id: EntityId) -> Activity {
+        match &world.entities.get(id).unwrap().kind {
+            EntityKind::Mob(mob) => mob.activity,
+            kind => panic!("not a mob: {kind:?}"),
+        }
+    }
+
+    #[test]
+    fn tame_mobs_follow_the_player_over_a_step() {
+        let step: Vec<([i32; 3], u8)> = (-16..32).map(|z| ([4, 1, z], 3)).collect();
+        let mut world = flat_world(&step);
+        let id = world.spawn_mob([0, 1, 0].into(), Temper::Tame);
+        let player = Player::new([10.5, 2.0, 0.5].into());
+
+        run(&mut world, &player, 100);
+
+        assert_eq!(activity(&world, id), Activity::Follow);
+        let distance = (position(&world, id) - player.position).magnitude();
+        assert!(distance <= FOLLOW_DISTANCE + 0.5, "{distance}");
+    }
+
+    #[test]
+    fn timid_mobs_run_away() {
+        let mut world = flat_world(&[]);
+        let id = world.spawn_mob([5, 1, 5].into(), Temper::Timid);
+        let player = Player::new([3.5, 1.0, 5.5].into());
+        let start = (position(&world, id) - player.position).magnitude();
+
+        run(&mut world, &player, 5);
+        assert_eq!(activity(&world, id), Activity::Flee);
+        run(&mut world, &player, 55);
+
+        let end = (position(&world, id) - player.position).magnitude();
+        assert!(end >= FLEE_RANGE, "{start} -> {end}");
+    }
+
+    #[test]
+    fn mobs_wander_when_nobody_is_around() {
+        let mut world = flat_world(&[]);
+        let id = world.spawn_mob([5, 1, 5].into(), Temper::Timid);
+        let mut player = Player::new([5.5, 1.0, 5.5].into());
+        player.mode = MovementMode::Spectator;
+        let start = position(&world, id);
+
+        run(&mut world, &player, 600);
+
+        assert_eq!(activity(&world, id), Activity::Wander);
+        assert_ne!(position(&world, id), start);
+        assert!(BlockPos::containing(position(&world, id)).y >= 1);
+    }
+}
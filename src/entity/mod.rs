@@ -0,0 +1,470 @@
+use std::{collections::BTreeMap, io::Write, mem};
+
+use anyhow::{anyhow, bail, Result};
+use cgmath::{Point3, Quaternion, Rad, Rotation3, Vector3};
+
+use crate::{
+    player::{
+        collision::{self, Aabb},
+        inventory::ItemStack,
+        GRAVITY, VOID_Y,
+    },
+    terrain::{
+        save::{read_f32, read_u32, read_u64, read_u8},
+        BlockPos, ChunkPos, World,
+    },
+    timestep::TICK_SECONDS,
+};
+
+pub mod item;
+pub mod mob;
+pub mod pathfind;
+
+/// Share of horizontal velocity kept each tick in the air.
+const AIR_DRAG: f32 = 0.98;
+/// Share of gravity that still pulls in a fluid.
+const FLUID_GRAVITY: f32 = 0.2;
+/// Share of velocity kept each tick in a fluid.
+const FLUID_DRAG: f32 = 0.8;
+
+/// Stays the same for as long as the entity exists, saving and loading included.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntityId(pub u64);
+
+/// Where an entity is and which way it faces.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    /// Bottom center.
+    pub position: Point3<f32>,
+    /// Turn around the vertical axis.
+    pub yaw: Rad<f32>,
+    /// Position and yaw after the tick before the last one, rendering interpolates from here.
+    previous_position: Point3<f32>,
+    previous_yaw: Rad<f32>,
+}
+
+impl Transform {
+    pub fn new(position: Point3<f32>, yaw: Rad<f32>) -> Self {
+        Self {
+            position,
+            yaw,
+            previous_position: position,
+            previous_yaw: yaw,
+        }
+    }
+
+    /// Position and yaw `alpha` of the way from the previous tick to the current one.
+    pub fn interpolated(&self, alpha: f32) -> (Point3<f32>, Rad<f32>) {
+        (
+            self.previous_position + (self.position - self.previous_position) * alpha,
+            self.previous_yaw + (self.yaw - self.previous_yaw) * alpha,
+        )
+    }
+}
+
+/// How an entity moves on its own every tick.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Velocity {
+    /// Blocks per second.
+    pub linear: Vector3<f32>,
+    /// Radians per second around the vertical axis.
+    pub spin: f32,
+    pub gravity: bool,
+    /// Share of horizontal velocity kept each tick on the ground.
+    pub friction: f32,
+}
+
+/// The box an entity collides with, standing on its position.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Body {
+    pub width: f32,
+    pub height: f32,
+}
+
+/// How an entity is drawn.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RenderModel {
+    /// A cube `size` wide textured like the block `item`, standing on the entity's position.
+    Cube { item: u8, size: f32 },
+}
+
+/// What an entity is, along with whatever only that kind needs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntityKind {
+    Item(ItemStack),
+    Mob(mob::Mob),
+}
+
+/// Anything in the world that is not a block or the player. Entities without a velocity stay
+/// put, without a body they pass through blocks and without a model they are not drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entity {
+    pub transform: Transform,
+    pub velocity: Option<Velocity>,
+    pub body: Option<Body>,
+    pub model: Option<RenderModel>,
+    pub kind: EntityKind,
+    /// Ticks since it was spawned.
+    pub age: u32,
+    /// Downward motion was stopped by a block on the last tick.
+    pub on_ground: bool,
+}
+
+impl Entity {
+    /// The chunk the entity belongs to, it is loaded, saved and unloaded with it.
+    pub fn chunk(&self) -> ChunkPos {
+        BlockPos::containing(self.transform.position).chunk()
+    }
+
+    /// Box of the body around the position, zero sized without a body.
+    pub fn aabb(&self) -> Aabb {
+        let body = self.body.unwrap_or(Body {
+            width: 0.0,
+            height: 0.0,
+        });
+        Aabb::from_feet(self.transform.position, body.width, body.height)
+    }
+
+    /// Middle of the model and its rotation `alpha` of the way through the tick, for rendering.
+    pub fn pose(&self, alpha: f32) -> (Point3<f32>, Quaternion<f32>) {
+        let (feet, yaw) = self.transform.interpolated(alpha);
+        let height = match self.model {
+            Some(RenderModel::Cube { size, .. }) => size,
+            None => 0.0,
+        };
+        (
+            feet + Vector3::new(0.0, height / 2.0, 0.0),
+            Quaternion::from_angle_y(yaw),
+        )
+    }
+
+    /// Too old or out of the world, removed at the end of the tick.
+    fn expired(&self) -> bool {
+        let too_old = match self.kind {
+            EntityKind::Item(_) => self.age >= item::DESPAWN_TICKS,
+            EntityKind::Mob(_) => false,
+        };
+        too_old || self.transform.position.y < VOID_Y
+    }
+
+    /// Falls, slides and turns for one tick, stopping against solid blocks if it has a body.
+    /// Bodies in a fluid sink slowly and are held back.
+    fn update(&mut self, world: &World) {
+        let transform = &mut self.transform;
+        transform.previous_position = transform.position;
+        transform.previous_yaw = transform.yaw;
+        self.age += 1;
+        let Some(velocity) = &mut self.velocity else {
+            return;
+        };
+
+        let in_fluid = self.body.is_some_and(|body| {
+            collision::in_fluid(
+                world,
+                &Aabb::from_feet(transform.position, body.width, body.height),
+            )
+        });
+        if velocity.gravity {
+            let gravity = if in_fluid {
+                GRAVITY * FLUID_GRAVITY
+            } else {
+                GRAVITY
+            };
+            velocity.linear.y -= gravity * TICK_SECONDS;
+        }
+        let motion = velocity.linear * TICK_SECONDS;
+        if let Some(body) = self.body {
+            let aabb = Aabb::from_feet(transform.position, body.width, body.height);
+            let collision = collision::move_and_collide(world, aabb, motion, 0.0);
+            transform.position = collision.aabb.feet();
+            for axis in 0..3 {
+                if collision.blocked[axis] {
+                    velocity.linear[axis] = 0.0;
+                }
+            }
+            self.on_ground = collision.on_ground;
+        } else {
+            transform.position += motion;
+        }
+
+        let friction = if in_fluid {
+            velocity.linear.y *= FLUID_DRAG;
+            FLUID_DRAG
+        } else if self.on_ground {
+            velocity.friction
+        } else {
+            AIR_DRAG
+        };
+        velocity.linear.x *= friction;
+        velocity.linear.z *= friction;
+        transform.yaw += Rad(velocity.spin * TICK_SECONDS);
+    }
+
+    /// Writes what changes while the entity lives, the rest comes from its kind when read back.
+    pub(crate) fn write(&self, data: &mut Vec<u8>) -> Result<()> {
+        match &self.kind {
+            EntityKind::Item(stack) => data.write_all(&[0, stack.item, stack.count])?,
+            EntityKind::Mob(mob) => {
+                data.write_all(&[1, mob.temper.id()])?;
+                data.write_all(&mob.rng_state().to_le_bytes())?;
+            }
+        }
+        let linear = self
+            .velocity
+            .map_or(Vector3::new(0.0, 0.0, 0.0), |v| v.linear);
+        let position = self.transform.position;
+        for value in [
+            position.x,
+            position.y,
+            position.z,
+            linear.x,
+            linear.y,
+            linear.z,
+            self.transform.yaw.0,
+        ] {
+            data.write_all(&value.to_le_bytes())?;
+        }
+        data.write_all(&self.age.to_le_bytes())?;
+        Ok(())
+    }
+
+    pub(crate) fn read(data: &mut &[u8]) -> Result<Self> {
+        let mut entity = match read_u8(data)? {
+            0 => {
+                let stack = ItemStack {
+                    item: read_u8(data)?,
+                    count: read_u8(data)?,
+                };
+                item::new(Point3::new(0.0, 0.0, 0.0), stack)
+            }
+            1 => {
+                let temper = read_u8(data)?;
+                let temper = mob::Temper::from_id(temper)
+                    .ok_or_else(|| anyhow!("unknown mob temper {temper}"))?;
+                mob::new(Point3::new(0.0, 0.0, 0.0), temper, read_u64(data)?)
+            }
+            kind => bail!("unknown entity kind {kind}"),
+        };
+        let mut values = [0.0; 7];
+        for value in &mut values {
+            *value = read_f32(data)?;
+        }
+        let [x, y, z, vx, vy, vz, yaw] = values;
+        entity.transform = Transform::new(Point3::new(x, y, z), Rad(yaw));
+        if let Some(velocity) = &mut entity.velocity {
+            velocity.linear = Vector3::new(vx, vy, vz);
+        }
+        entity.age = read_u32(data)?;
+        Ok(entity)
+    }
+}
+
+/// Every entity in the world by id, kept in id order so updates are deterministic.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Entities {
+    entities: BTreeMap<EntityId, Entity>,
+    next_id: u64,
+}
+
+impl Entities {
+    /// Adds the entity under a new id.
+    pub fn spawn(&mut self, entity: Entity) -> EntityId {
+        let id = EntityId(self.next_id);
+        self.next_id += 1;
+        self.entities.insert(id, entity);
+        id
+    }
+
+    /// Adds an entity that already has an id, like one read from a save.
+    pub fn insert(&mut self, id: EntityId, entity: Entity) {
+        self.next_id = self.next_id.max(id.0 + 1);
+        self.entities.insert(id, entity);
+    }
+
+    pub fn remove(&mut self, id: EntityId) -> Option<Entity> {
+        self.entities.remove(&id)
+    }
+
+    pub fn get(&self, id: EntityId) -> Option<&Entity> {
+        self.entities.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut Entity> {
+        self.entities.get_mut(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, &Entity)> {
+        self.entities.iter().map(|(id, entity)| (*id, entity))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (EntityId, &mut Entity)> {
+        self.entities.iter_mut().map(|(id, entity)| (*id, entity))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// The entities a chunk owns.
+    pub fn in_chunk(&self, chunk_pos: ChunkPos) -> impl Iterator<Item = (EntityId, &Entity)> {
+        self.iter()
+            .filter(move |(_, entity)| entity.chunk() == chunk_pos)
+    }
+
+    /// Takes out every entity a chunk owns.
+    pub fn remove_chunk(&mut self, chunk_pos: ChunkPos) -> Vec<(EntityId, Entity)> {
+        let ids: Vec<EntityId> = self.in_chunk(chunk_pos).map(|(id, _)| id).collect();
+        ids.into_iter()
+            .filter_map(|id| Some((id, self.entities.remove(&id)?)))
+            .collect()
+    }
+
+    /// The id the next spawned entity gets, saved so ids are never reused.
+    pub fn next_id(&self) -> u64 {
+        self.next_id
+    }
+
+    pub(crate) fn set_next_id(&mut self, next_id: u64) {
+        self.next_id = self.next_id.max(next_id);
+    }
+}
+
+impl World {
+    /// Moves every entity in a loaded chunk, then removes expired ones and lets each kind act
+    /// on the others. Entities stop at the edge of the loaded chunks so they are always saved
+    /// and unloaded with the chunk they are in.
+    pub(crate) fn tick_entities(&mut self) {
+        let mut entities = mem::take(&mut self.entities);
+        for entity in entities.entities.values_mut() {
+            if !self.chunks.contains_key(&entity.chunk()) {
+                continue;
+            }
+            entity.update(self);
+            if !self.chunks.contains_key(&entity.chunk()) {
+                entity.transform.position = entity.transform.previous_position;
+                if let Some(velocity) = &mut entity.velocity {
+                    velocity.linear = Vector3::new(0.0, 0.0, 0.0);
+                }
+            }
+        }
+        entities.entities.retain(|_, entity| !entity.expired());
+        item::merge(&mut entities);
+        self.entities = entities;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack(item: u8, count: u8) -> ItemStack {
+        ItemStack { item, count }
+    }
+
+    #[test]
+    fn ids_are_never_reused() {
+        let mut entities = Entities::default();
+        let first = entities.spawn(item::new([0.5, 1.0, 0.5].into(), stack(2, 1)));
+        entities.remove(first);
+        let second = entities.spawn(item::new([0.5, 1.0, 0.5].into(), stack(2, 1)));
+
+        entities.insert(EntityId(10), item::new([0.5, 1.0, 0.5].into(), stack(2, 1)));
+        let third = entities.spawn(item::new([0.5, 1.0, 0.5].into(), stack(2, 1)));
+
+        assert_ne!(first, second);
+        assert_eq!(third, EntityId(11));
+    }
+
+    #[test]
+    fn entities_belong_to_the_chunk_they_are_in() {
+        let mut entities = Entities::default();
+        let here = entities.spawn(item::new([0.5, 1.0, 0.5].into(), stack(2, 1)));
+        let there = entities.spawn(item::new([-0.5, 20.0, 0.5].into(), stack(3, 1)));
+
+        let owned: Vec<EntityId> = entities
+            .in_chunk([-1, 1, 0].into())
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(owned, vec![there]);
+
+        let removed = entities.remove_chunk([0, 0, 0].into());
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].0, here);
+        assert_eq!(entities.len(), 1);
+    }
+
+    #[test]
+    fn entities_without_a_body_pass_through_blocks() {
+        let mut world = World::with_blocks(&[([0, 0, 0], 3)]);
+        let mut entity = item::new([0.5, 1.5, 0.5].into(), stack(2, 1));
+        entity.body = None;
+        let id = world.entities.spawn(entity);
+
+        for _ in 0..10 {
+            world.tick_entities();
+        }
+
+        assert!(world.entities.get(id).unwrap().transform.position.y < 0.0);
+    }
+
+    #[test]
+    fn entities_in_unloaded_chunks_stay_put() {
+        let mut world = World::with_blocks(&[]);
+        let far = Point3::new(100.5, 1.0, 0.5);
+        let id = world.entities.spawn(item::new(far, stack(2, 1)));
+
+        world.tick_entities();
+
+        let entity = world.entities.get(id).unwrap();
+        assert_eq!(entity.transform.position, far);
+        assert_eq!(entity.age, 0);
+    }
+
+    #[test]
+    fn entities_stop_at_the_edge_of_the_loaded_chunks() {
+        let mut world = World::with_blocks(&[]);
+        let edge = Point3::new(0.5, -15.5, 0.5);
+        let mut entity = item::new(edge, stack(2, 1));
+        entity.body = None;
+        let id = world.entities.spawn(entity);
+
+        for _ in 0..10 {
+            world.tick_entities();
+        }
+
+        let entity = world.entities.get(id).unwrap();
+        assert_eq!(entity.chunk(), [0, -1, 0].into());
+        assert!(entity.transform.position.y >= -16.0);
+    }
+
+    #[test]
+    fn entities_survive_writing_and_reading() {
+        let mut entity = item::new([3.5, 2.0, -1.5].into(), stack(4, 7));
+        entity.transform = Transform::new([3.5, 2.0, -1.5].into(), Rad(1.25));
+        entity.age = 321;
+        if let Some(velocity) = &mut entity.velocity {
+            velocity.linear = Vector3::new(0.5, -2.0, 0.25);
+        }
+        let mut data = Vec::new();
+        entity.write(&mut data).unwrap();
+
+        let read = Entity::read(&mut data.as_slice()).unwrap();
+
+        assert_eq!(read, entity);
+    }
+
+    #[test]
+    fn mobs_survive_writing_and_reading() {
+        let entity = mob::new([3.5, 2.0, -1.5].into(), mob::Temper::Timid, 42);
+        let mut data = Vec::new();
+        entity.write(&mut data).unwrap();
+
+        let read = Entity::read(&mut data.as_slice()).unwrap();
+
+        assert_eq!(read, entity);
+    }
+}
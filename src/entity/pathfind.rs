@@ -0,0 +1,390 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use cgmath::Vector3;
+
+use crate::terrain::{BlockPos, World};
+
+/// Cost of walking or swimming one block, everything else is a multiple of it so costs stay
+/// integers and ties break the same way every time.
+const STEP_COST: u32 = 10;
+/// Jumping up a block costs this on top of the step.
+const STEP_UP_COST: u32 = 10;
+/// Each block fallen costs this on top of the step.
+const DROP_COST: u32 = 10;
+/// Swimming is slower than walking.
+const SWIM_COST: u32 = 20;
+
+const HORIZONTAL: [Vector3<i32>; 4] = [
+    Vector3::new(1, 0, 0),
+    Vector3::new(-1, 0, 0),
+    Vector3::new(0, 0, 1),
+    Vector3::new(0, 0, -1),
+];
+const UP: Vector3<i32> = Vector3::new(0, 1, 0);
+
+/// What pathfinding needs to know about the world, so it can run on anything that has blocks.
+pub trait WorldView {
+    fn is_solid(&self, position: BlockPos) -> bool;
+    fn is_fluid(&self, position: BlockPos) -> bool;
+}
+
+impl WorldView for World {
+    fn is_solid(&self, position: BlockPos) -> bool {
+        World::is_solid(self, position)
+    }
+
+    fn is_fluid(&self, position: BlockPos) -> bool {
+        World::is_fluid(self, position)
+    }
+}
+
+/// What a walker can do, paths are found for its feet block.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Walker {
+    /// Blocks of headroom it needs.
+    pub height: i32,
+    /// Furthest it is willing to fall.
+    pub max_drop: i32,
+    pub swims: bool,
+    /// Nodes searched before giving up on a goal.
+    pub max_nodes: usize,
+}
+
+impl Walker {
+    /// Nothing solid where the body would be.
+    fn fits(&self, world: &impl WorldView, feet: BlockPos) -> bool {
+        (0..self.height).all(|y| !world.is_solid(feet + UP * y))
+    }
+
+    fn swimming(&self, world: &impl WorldView, feet: BlockPos) -> bool {
+        self.swims && world.is_fluid(feet)
+    }
+
+    /// The body fits and there is ground to stand on or water to swim in.
+    pub fn can_stand(&self, world: &impl WorldView, feet: BlockPos) -> bool {
+        self.fits(world, feet)
+            && (world.is_solid(feet - UP) || self.swimming(world, feet))
+            && (self.swims || !world.is_fluid(feet))
+    }
+
+    /// The nearest spot within `range` blocks above or below `near` in its column to stand in.
+    pub fn stand_near(
+        &self,
+        world: &impl WorldView,
+        near: BlockPos,
+        range: i32,
+    ) -> Option<BlockPos> {
+        (0..=range)
+            .flat_map(|offset| [near - UP * offset, near + UP * offset])
+            .find(|&feet| self.can_stand(world, feet))
+    }
+
+    /// Where the walker can get to from `feet` in one move, and what it costs.
+    fn moves(&self, world: &impl WorldView, feet: BlockPos) -> Vec<(BlockPos, u32)> {
+        let mut moves = Vec::new();
+        let swimming = self.swimming(world, feet);
+        let step = if swimming { SWIM_COST } else { STEP_COST };
+        for direction in HORIZONTAL {
+            let next = feet + direction;
+            if self.can_stand(world, next) {
+                moves.push((next, step));
+                continue;
+            }
+            // jumping needs room above the current spot as well as the next one
+            let up = next + UP;
+            if self.can_stand(world, up) && self.fits(world, feet + UP) {
+                moves.push((up, step + STEP_UP_COST));
+                continue;
+            }
+            if !self.fits(world, next) {
+                continue;
+            }
+            let landing = (1..=self.max_drop)
+                .map(|fall| (fall, next - UP * fall))
+                .take_while(|(_, below)| self.fits(world, *below))
+                .find(|(_, below)| self.can_stand(world, *below));
+            if let Some((fall, below)) = landing {
+                moves.push((below, step + DROP_COST * fall as u32));
+            }
+        }
+        if swimming {
+            for next in [feet + UP, feet - UP] {
+                if self.can_stand(world, next) {
+                    moves.push((next, SWIM_COST));
+                }
+            }
+        }
+        moves
+    }
+}
+
+fn distance(a: BlockPos, b: BlockPos) -> u32 {
+    let d = b - a;
+    (d.x.unsigned_abs() + d.y.unsigned_abs() + d.z.unsigned_abs()) * STEP_COST
+}
+
+/// How a search ended, or that it needs more ticks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchStatus {
+    Searching,
+    /// The feet blocks to walk through after the start, ending at the goal.
+    Found(Vec<BlockPos>),
+    Failed,
+}
+
+/// An A* search that can be spread over several ticks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Search {
+    walker: Walker,
+    goal: BlockPos,
+    /// Nodes to look at by estimated total cost, cheapest first.
+    open: BTreeSet<(u32, BlockPos)>,
+    /// Cheapest known cost to every reached node and where it was reached from.
+    reached: HashMap<BlockPos, (u32, Option<BlockPos>)>,
+    closed: HashSet<BlockPos>,
+}
+
+impl Search {
+    pub fn new(walker: Walker, start: BlockPos, goal: BlockPos) -> Self {
+        Self {
+            walker,
+            goal,
+            open: BTreeSet::from([(distance(start, goal), start)]),
+            reached: HashMap::from([(start, (0, None))]),
+            closed: HashSet::new(),
+        }
+    }
+
+    pub fn goal(&self) -> BlockPos {
+        self.goal
+    }
+
+    /// Looks at up to `budget` more nodes.
+    pub fn step(&mut self, world: &impl WorldView, budget: usize) -> SearchStatus {
+        for _ in 0..budget {
+            if self.closed.len() >= self.walker.max_nodes {
+                return SearchStatus::Failed;
+            }
+            let Some((_, node)) = self.open.pop_first() else {
+                return SearchStatus::Failed;
+            };
+            if node == self.goal {
+                return SearchStatus::Found(self.path_to(node));
+            }
+            if !self.closed.insert(node) {
+                continue;
+            }
+            let cost = self.reached[&node].0;
+            for (next, step) in self.walker.moves(world, node) {
+                let next_cost = cost + step;
+                if self.closed.contains(&next)
+                    || self
+                        .reached
+                        .get(&next)
+                        .is_some_and(|(known, _)| *known <= next_cost)
+                {
+                    continue;
+                }
+                self.reached.insert(next, (next_cost, Some(node)));
+                self.open
+                    .insert((next_cost + distance(next, self.goal), next));
+            }
+        }
+        SearchStatus::Searching
+    }
+
+    fn path_to(&self, end: BlockPos) -> Vec<BlockPos> {
+        let mut path = vec![end];
+        while let Some((_, Some(from))) = self.reached.get(path.last().unwrap()) {
+            path.push(*from);
+        }
+        path.pop();
+        path.reverse();
+        path
+    }
+}
+
+/// Searches until done, for when the whole budget can be spent at once.
+pub fn find_path(
+    world: &impl WorldView,
+    walker: Walker,
+    start: BlockPos,
+    goal: BlockPos,
+) -> Option<Vec<BlockPos>> {
+    let mut search = Search::new(walker, start, goal);
+    loop {
+        match search.step(world, walker.max_nodes) {
+            SearchStatus::Searching => continue,
+            SearchStatus::Found(path) => return Some(path),
+            SearchStatus::Failed => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALKER: Walker = Walker {
+        height: 2,
+        max_drop: 3,
+        swims: true,
+        max_nodes: 2000,
+    };
+
+    /// Blocks built by hand on a stone floor at y = 0 spanning x and z from -10 to 10.
+    #[derive(Default)]
+    struct Blocks {
+        solid: HashSet<BlockPos>,
+        fluid: HashSet<BlockPos>,
+        holes: HashSet<BlockPos>,
+    }
+
+    impl Blocks {
+        fn wall(mut self, from: [i32; 3], to: [i32; 3]) -> Self {
+            for position in cuboid(from, to) {
+                self.solid.insert(position);
+            }
+            self
+        }
+
+        fn water(mut self, from: [i32; 3], to: [i32; 3]) -> Self {
+            for position in cuboid(from, to) {
+                self.fluid.insert(position);
+                self.holes.insert(position);
+            }
+            self
+        }
+
+        fn hole(mut self, from: [i32; 3], to: [i32; 3]) -> Self {
+            self.holes.extend(cuboid(from, to));
+            self
+        }
+    }
+
+    fn cuboid(from: [i32; 3], to: [i32; 3]) -> impl Iterator<Item = BlockPos> {
+        (from[0]..=to[0]).flat_map(move |x| {
+            (from[1]..=to[1])
+                .flat_map(move |y| (from[2]..=to[2]).map(move |z| BlockPos::new(x, y, z)))
+        })
+    }
+
+    impl WorldView for Blocks {
+        fn is_solid(&self, position: BlockPos) -> bool {
+            let floor = position.y <= 0
+                && position.y >= -10
+                && position.x.abs() <= 10
+                && position.z.abs() <= 10;
+            self.solid.contains(&position) || (floor && !self.holes.contains(&position))
+        }
+
+        fn is_fluid(&self, position: BlockPos) -> bool {
+            self.fluid.contains(&position)
+        }
+    }
+
+    fn path(world: &Blocks, walker: Walker, goal: [i32; 3]) -> Option<Vec<BlockPos>> {
+        find_path(world, walker, BlockPos::new(0, 1, 0), goal.into())
+    }
+
+    #[test]
+    fn walks_straight_across_flat_ground() {
+        let path = path(&Blocks::default(), WALKER, [4, 1, 0]).unwrap();
+
+        let expected: Vec<BlockPos> = (1..=4).map(|x| BlockPos::new(x, 1, 0)).collect();
+        assert_eq!(path, expected);
+    }
+
+    #[test]
+    fn goes_around_walls() {
+        let world = Blocks::default().wall([2, 1, -3], [2, 2, 3]);
+
+        let path = path(&world, WALKER, [4, 1, 0]).unwrap();
+
+        assert_eq!(path.len(), 4 + 2 * 4);
+        assert!(path.iter().all(|position| !world.is_solid(*position)));
+    }
+
+    #[test]
+    fn steps_up_one_block_but_not_two() {
+        let step = Blocks::default().wall([2, 1, -10], [10, 1, 10]);
+        let path = path(&step, WALKER, [4, 2, 0]).unwrap();
+        assert_eq!(path[1], BlockPos::new(2, 2, 0));
+
+        let cliff = Blocks::default().wall([2, 1, -10], [10, 2, 10]);
+        assert_eq!(super::tests::path(&cliff, WALKER, [4, 3, 0]), None);
+    }
+
+    #[test]
+    fn no_jumping_with_a_ceiling_overhead() {
+        let world = Blocks::default()
+            .wall([2, 1, -10], [10, 1, 10])
+            .wall([-10, 3, -10], [10, 3, 10]);
+
+        assert_eq!(path(&world, WALKER, [4, 2, 0]), None);
+    }
+
+    #[test]
+    fn drops_only_as_far_as_allowed() {
+        let pit = Blocks::default().hole([2, -2, -10], [10, 0, 10]);
+
+        let path = path(&pit, WALKER, [4, -2, 0]).unwrap();
+        assert_eq!(path[1], BlockPos::new(2, -2, 0));
+
+        let careful = Walker {
+            max_drop: 2,
+            ..WALKER
+        };
+        assert_eq!(super::tests::path(&pit, careful, [4, -2, 0]), None);
+    }
+
+    #[test]
+    fn swims_across_water() {
+        let world = Blocks::default().water([2, -2, -10], [4, 0, 10]);
+
+        let path = path(&world, WALKER, [6, 1, 0]).unwrap();
+        assert!(path.iter().any(|position| world.is_fluid(*position)));
+
+        let landlubber = Walker {
+            swims: false,
+            max_drop: 0,
+            ..WALKER
+        };
+        assert_eq!(super::tests::path(&world, landlubber, [6, 1, 0]), None);
+    }
+
+    #[test]
+    fn searching_is_spread_over_ticks() {
+        let world = Blocks::default().wall([2, 1, -3], [2, 2, 3]);
+        let mut search = Search::new(WALKER, BlockPos::new(0, 1, 0), BlockPos::new(4, 1, 0));
+
+        let mut ticks = 1;
+        let path = loop {
+            match search.step(&world, 5) {
+                SearchStatus::Searching => ticks += 1,
+                SearchStatus::Found(path) => break path,
+                SearchStatus::Failed => panic!("no path"),
+            }
+        };
+
+        assert!(ticks > 1);
+        assert_eq!(
+            Some(path),
+            find_path(&world, WALKER, [0, 1, 0].into(), [4, 1, 0].into())
+        );
+    }
+
+    #[test]
+    fn gives_up_after_too_many_nodes() {
+        // on top of a tower too tall to climb
+        let world = Blocks::default().wall([3, 1, -1], [5, 3, 1]);
+        let walker = Walker {
+            max_nodes: 50,
+            ..WALKER
+        };
+        let mut search = Search::new(walker, BlockPos::new(0, 1, 0), BlockPos::new(4, 4, 0));
+
+        assert_eq!(search.step(&world, 1000), SearchStatus::Failed);
+        assert!(search.closed.len() <= 50);
+    }
+}
@@ -0,0 +1,611 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::Path,
+};
+
+use anyhow::{anyhow, bail, Result};
+use winit::keyboard::KeyCode;
+
+/// Number of hotbar slots that can be picked directly.
+pub const HOTBAR_SLOTS: u8 = 9;
+
+/// Something the player can do, independent of the key or button it is bound to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Sneak,
+    Sprint,
+    CycleMode,
+    SwitchGameMode,
+    Break,
+    Place,
+    /// Takes the block under the crosshair into the hand.
+    PickBlock,
+    /// Crafts with the item in the hand.
+    Craft,
+    /// Sets off the explosive block under the crosshair.
+    Ignite,
+    /// Picks a slot of the hotbar, counted from 0.
+    HotbarSlot(u8),
+}
+
+impl Action {
+    /// Every action, in the order they are written to the bindings file.
+    pub fn all() -> impl Iterator<Item = Self> {
+        [
+            Self::MoveForward,
+            Self::MoveBackward,
+            Self::MoveLeft,
+            Self::MoveRight,
+            Self::Jump,
+            Self::Sneak,
+            Self::Sprint,
+            Self::CycleMode,
+            Self::SwitchGameMode,
+            Self::Break,
+            Self::Place,
+            Self::PickBlock,
+            Self::Craft,
+            Self::Ignite,
+        ]
+        .into_iter()
+        .chain((0..HOTBAR_SLOTS).map(Self::HotbarSlot))
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all().find(|action| action.to_string() == name)
+    }
+
+    /// Happens once per press rather than for as long as the input is held.
+    pub fn is_one_shot(self) -> bool {
+        matches!(
+            self,
+            Self::CycleMode
+                | Self::SwitchGameMode
+                | Self::Place
+                | Self::PickBlock
+                | Self::Craft
+                | Self::Ignite
+                | Self::HotbarSlot(_)
+        )
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HotbarSlot(slot) => write!(f, "HotbarSlot{slot}"),
+            action => write!(f, "{action:?}"),
+        }
+    }
+}
+
+/// A key or mouse button an action can be bound to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Input {
+    Key(KeyCode),
+    /// Raw button id as reported by the mouse, 0 is usually left and 1 right.
+    Mouse(u32),
+}
+
+impl Input {
+    /// Parses the names written by `Display`, like `KeyW` or `Mouse1`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Some(button) = name.strip_prefix("Mouse") {
+            return button.parse().ok().map(Self::Mouse);
+        }
+        KEYS.iter()
+            .find(|key| format!("{key:?}") == name)
+            .map(|key| Self::Key(*key))
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(key) => write!(f, "{key:?}"),
+            Self::Mouse(button) => write!(f, "Mouse{button}"),
+        }
+    }
+}
+
+/// An input can only trigger one action.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub input: Input,
+    pub bound_to: Action,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is already bound to {}", self.input, self.bound_to)
+    }
+}
+
+impl std::error::Error for Conflict {}
+
+/// Which input triggers which action. An action can have several inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bindings {
+    actions: HashMap<Input, Action>,
+}
+
+impl Bindings {
+    pub fn empty() -> Self {
+        Self {
+            actions: HashMap::new(),
+        }
+    }
+
+    pub fn action(&self, input: Input) -> Option<Action> {
+        self.actions.get(&input).copied()
+    }
+
+    /// Like `action`, but one-shot actions ignore the repeats the OS sends while a key is held.
+    pub fn key_action(&self, input: Input, repeat: bool) -> Option<Action> {
+        self.action(input)
+            .filter(|action| !(repeat && action.is_one_shot()))
+    }
+
+    /// Inputs bound to `action`, in no particular order.
+    pub fn inputs(&self, action: Action) -> impl Iterator<Item = Input> + '_ {
+        self.actions
+            .iter()
+            .filter(move |(_, bound)| **bound == action)
+            .map(|(input, _)| *input)
+    }
+
+    /// Adds `input` to the inputs of `action`. Fails if another action already uses it.
+    pub fn bind(&mut self, action: Action, input: Input) -> Result<(), Conflict> {
+        match self.action(input) {
+            Some(bound_to) if bound_to != action => Err(Conflict { input, bound_to }),
+            _ => {
+                self.actions.insert(input, action);
+                Ok(())
+            }
+        }
+    }
+
+    /// Makes `input` the only input of `action`. Nothing changes on a conflict.
+    pub fn rebind(&mut self, action: Action, input: Input) -> Result<(), Conflict> {
+        if let Some(bound_to) = self.action(input).filter(|bound| *bound != action) {
+            return Err(Conflict { input, bound_to });
+        }
+        self.actions.retain(|_, bound| *bound != action);
+        self.actions.insert(input, action);
+        Ok(())
+    }
+
+    pub fn unbind(&mut self, input: Input) -> Option<Action> {
+        self.actions.remove(&input)
+    }
+
+    /// Reads bindings written as one `Action = Input, Input` line per action. Actions missing
+    /// from the text are left unbound.
+    pub fn parse(text: &str) -> Result<Self> {
+        Self::parse_with_actions(text).map(|(bindings, _)| bindings)
+    }
+
+    /// `parse`, along with every action that has a line in the text, bound or not.
+    fn parse_with_actions(text: &str) -> Result<(Self, HashSet<Action>)> {
+        let mut bindings = Self::empty();
+        let mut listed = HashSet::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let line_error = |message: String| anyhow!("line {}: {message}", number + 1);
+            let (action, inputs) = line
+                .split_once('=')
+                .ok_or_else(|| line_error("expected `Action = Input, ...`".into()))?;
+            let action = Action::from_name(action.trim())
+                .ok_or_else(|| line_error(format!("unknown action {}", action.trim())))?;
+            listed.insert(action);
+            for input in inputs.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+                let input = Input::from_name(input)
+                    .ok_or_else(|| line_error(format!("unknown key or button {input}")))?;
+                bindings
+                    .bind(action, input)
+                    .map_err(|conflict| line_error(conflict.to_string()))?;
+            }
+        }
+        Ok((bindings, listed))
+    }
+
+    /// Gives the actions not in `listed` their default inputs, those that are still free.
+    fn add_missing_defaults(&mut self, listed: &HashSet<Action>) {
+        for (input, action) in Self::default().actions {
+            if !listed.contains(&action) && self.action(input).is_none() {
+                self.actions.insert(input, action);
+            }
+        }
+    }
+
+    /// Bindings in the format `parse` reads, inputs of each action sorted by name.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for action in Action::all() {
+            let mut inputs: Vec<String> = self.inputs(action).map(|i| i.to_string()).collect();
+            inputs.sort();
+            text += &format!("{action} = {}\n", inputs.join(", "));
+        }
+        text
+    }
+
+    /// Loads the bindings file, writing the defaults there first if it does not exist yet.
+    /// Actions the file does not list yet, like ones added since it was written, get their
+    /// default inputs where those are free, and the file is rewritten to list them.
+    pub fn load_or_create(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                let (mut bindings, listed) = Self::parse_with_actions(&text)?;
+                if Action::all().any(|action| !listed.contains(&action)) {
+                    bindings.add_missing_defaults(&listed);
+                    fs::write(path, bindings.to_text())?;
+                }
+                Ok(bindings)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let bindings = Self::default();
+                fs::write(path, bindings.to_text())?;
+                Ok(bindings)
+            }
+            Err(e) => bail!(e),
+        }
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use KeyCode::*;
+        let hotbar = [
+            Digit1, Digit2, Digit3, Digit4, Digit5, Digit6, Digit7, Digit8, Digit9,
+        ];
+        let keys = [
+            (Action::MoveForward, KeyW),
+            (Action::MoveForward, ArrowUp),
+            (Action::MoveBackward, KeyS),
+            (Action::MoveBackward, ArrowDown),
+            (Action::MoveLeft, KeyA),
+            (Action::MoveLeft, ArrowLeft),
+            (Action::MoveRight, KeyD),
+            (Action::MoveRight, ArrowRight),
+            (Action::Jump, Space),
+            (Action::Sneak, ShiftLeft),
+            (Action::Sprint, ControlLeft),
+            (Action::CycleMode, F4),
+            (Action::SwitchGameMode, KeyG),
+            (Action::Craft, KeyC),
+            (Action::Ignite, KeyX),
+        ]
+        .into_iter()
+        .chain(
+            (0..)
+                .zip(hotbar)
+                .map(|(slot, key)| (Action::HotbarSlot(slot), key)),
+        );
+
+        let buttons = [
+            (Action::Break, 0),
+            (Action::Place, 1),
+            (Action::PickBlock, 2),
+        ];
+
+        let mut bindings = Self::empty();
+        let inputs = keys
+            .map(|(action, key)| (action, Input::Key(key)))
+            .chain(buttons.map(|(action, button)| (action, Input::Mouse(button))));
+        for (action, input) in inputs {
+            bindings
+                .bind(action, input)
+                .expect("default bindings conflict");
+        }
+        bindings
+    }
+}
+
+/// Keys that can be named in the bindings file. Escape is not one, it always saves and quits.
+const KEYS: &[KeyCode] = {
+    use KeyCode::*;
+    &[
+        KeyA,
+        KeyB,
+        KeyC,
+        KeyD,
+        KeyE,
+        KeyF,
+        KeyG,
+        KeyH,
+        KeyI,
+        KeyJ,
+        KeyK,
+        KeyL,
+        KeyM,
+        KeyN,
+        KeyO,
+        KeyP,
+        KeyQ,
+        KeyR,
+        KeyS,
+        KeyT,
+        KeyU,
+        KeyV,
+        KeyW,
+        KeyX,
+        KeyY,
+        KeyZ,
+        Digit0,
+        Digit1,
+        Digit2,
+        Digit3,
+        Digit4,
+        Digit5,
+        Digit6,
+        Digit7,
+        Digit8,
+        Digit9,
+        Numpad0,
+        Numpad1,
+        Numpad2,
+        Numpad3,
+        Numpad4,
+        Numpad5,
+        Numpad6,
+        Numpad7,
+        Numpad8,
+        Numpad9,
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        F11,
+        F12,
+        ArrowUp,
+        ArrowDown,
+        ArrowLeft,
+        ArrowRight,
+        Space,
+        Enter,
+        Tab,
+        Backspace,
+        CapsLock,
+        ShiftLeft,
+        ShiftRight,
+        ControlLeft,
+        ControlRight,
+        AltLeft,
+        AltRight,
+        Insert,
+        Delete,
+        Home,
+        End,
+        PageUp,
+        PageDown,
+        Minus,
+        Equal,
+        Backquote,
+        BracketLeft,
+        BracketRight,
+        Backslash,
+        Semicolon,
+        Quote,
+        Comma,
+        Period,
+        Slash,
+    ]
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_survive_being_written_and_read_back() {
+        let defaults = Bindings::default();
+
+        let parsed = Bindings::parse(&defaults.to_text()).unwrap();
+
+        assert_eq!(parsed, defaults);
+        assert_eq!(
+            parsed.action(Input::Key(KeyCode::KeyW)),
+            Some(Action::MoveForward)
+        );
+        assert_eq!(parsed.action(Input::Mouse(1)), Some(Action::Place));
+        assert_eq!(
+            parsed.action(Input::Key(KeyCode::Digit9)),
+            Some(Action::HotbarSlot(8))
+        );
+    }
+
+    #[test]
+    fn every_action_has_a_default_binding() {
+        let defaults = Bindings::default();
+
+        for action in Action::all() {
+            assert!(defaults.inputs(action).next().is_some(), "{action}");
+        }
+    }
+
+    #[test]
+    fn config_text_binds_keys_and_buttons() {
+        let text = "
+            # movement
+            MoveForward = KeyI, ArrowUp
+            Jump = Mouse4   # side button
+            HotbarSlot3 = KeyQ
+        ";
+
+        let bindings = Bindings::parse(text).unwrap();
+
+        assert_eq!(
+            bindings.action(Input::Key(KeyCode::KeyI)),
+            Some(Action::MoveForward)
+        );
+        assert_eq!(
+            bindings.action(Input::Key(KeyCode::ArrowUp)),
+            Some(Action::MoveForward)
+        );
+        assert_eq!(bindings.action(Input::Mouse(4)), Some(Action::Jump));
+        assert_eq!(
+            bindings.action(Input::Key(KeyCode::KeyQ)),
+            Some(Action::HotbarSlot(3))
+        );
+        assert_eq!(bindings.action(Input::Key(KeyCode::KeyW)), None);
+    }
+
+    #[test]
+    fn config_errors_name_the_line() {
+        let unknown_action = Bindings::parse("Jump = Space\nFly = KeyF").unwrap_err();
+        let unknown_key = Bindings::parse("Jump = Spacebar").unwrap_err();
+        let conflict = Bindings::parse("Jump = Space\n\nSneak = Space").unwrap_err();
+        let no_equals = Bindings::parse("Jump Space").unwrap_err();
+
+        assert_eq!(unknown_action.to_string(), "line 2: unknown action Fly");
+        assert_eq!(
+            unknown_key.to_string(),
+            "line 1: unknown key or button Spacebar"
+        );
+        assert_eq!(
+            conflict.to_string(),
+            "line 3: Space is already bound to Jump"
+        );
+        assert!(no_equals.to_string().starts_with("line 1:"));
+    }
+
+    #[test]
+    fn binding_an_input_of_another_action_conflicts() {
+        let mut bindings = Bindings::default();
+
+        let conflict = bindings.bind(Action::Jump, Input::Key(KeyCode::KeyW));
+
+        assert_eq!(
+            conflict,
+            Err(Conflict {
+                input: Input::Key(KeyCode::KeyW),
+                bound_to: Action::MoveForward
+            })
+        );
+        assert_eq!(
+            bindings.action(Input::Key(KeyCode::KeyW)),
+            Some(Action::MoveForward)
+        );
+        // binding the same pair again is fine
+        assert_eq!(
+            bindings.bind(Action::MoveForward, Input::Key(KeyCode::KeyW)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rebinding_replaces_the_old_inputs() {
+        let mut bindings = Bindings::default();
+
+        bindings
+            .rebind(Action::Jump, Input::Key(KeyCode::KeyJ))
+            .unwrap();
+
+        assert_eq!(
+            bindings.action(Input::Key(KeyCode::KeyJ)),
+            Some(Action::Jump)
+        );
+        assert_eq!(bindings.action(Input::Key(KeyCode::Space)), None);
+        assert_eq!(bindings.inputs(Action::Jump).count(), 1);
+    }
+
+    #[test]
+    fn rebinding_onto_a_used_input_changes_nothing() {
+        let mut bindings = Bindings::default();
+
+        let result = bindings.rebind(Action::Jump, Input::Mouse(0));
+
+        assert!(result.is_err());
+        assert_eq!(bindings, Bindings::default());
+    }
+
+    #[test]
+    fn freed_input_can_be_bound_again() {
+        let mut bindings = Bindings::default();
+
+        assert_eq!(
+            bindings.unbind(Input::Key(KeyCode::ShiftLeft)),
+            Some(Action::Sneak)
+        );
+        bindings
+            .bind(Action::Jump, Input::Key(KeyCode::ShiftLeft))
+            .unwrap();
+
+        assert_eq!(
+            bindings.action(Input::Key(KeyCode::ShiftLeft)),
+            Some(Action::Jump)
+        );
+    }
+
+    #[test]
+    fn every_key_name_parses_back() {
+        for key in KEYS {
+            let input = Input::Key(*key);
+            assert_eq!(Input::from_name(&input.to_string()), Some(input));
+        }
+        assert_eq!(Input::from_name("Mouse12"), Some(Input::Mouse(12)));
+        assert_eq!(Input::from_name("Mouse"), None);
+        assert_eq!(Input::from_name("Escape"), None);
+    }
+
+    #[test]
+    fn a_held_key_crafts_once_but_keeps_moving() {
+        let bindings = Bindings::default();
+        let repeats = [false, true, true, true];
+
+        let actions = |key| -> Vec<Action> {
+            repeats
+                .iter()
+                .filter_map(|&repeat| bindings.key_action(Input::Key(key), repeat))
+                .collect()
+        };
+
+        assert_eq!(actions(KeyCode::KeyC), vec![Action::Craft]);
+        assert_eq!(actions(KeyCode::KeyW), vec![Action::MoveForward; 4]);
+    }
+
+    #[test]
+    fn actions_missing_from_the_file_get_their_free_defaults() {
+        let path =
+            std::env::temp_dir().join(format!("game-wgpu-controls-{}.cfg", std::process::id()));
+        // an old file from before crafting and igniting, with X taken and Sneak unbound on purpose
+        fs::write(&path, "Jump = Space, KeyX\nSneak =\n").unwrap();
+
+        let bindings = Bindings::load_or_create(&path).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let reloaded = Bindings::parse(&text).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            bindings.action(Input::Key(KeyCode::KeyC)),
+            Some(Action::Craft)
+        );
+        assert_eq!(
+            bindings.action(Input::Key(KeyCode::KeyX)),
+            Some(Action::Jump)
+        );
+        assert_eq!(bindings.inputs(Action::Ignite).next(), None);
+        assert_eq!(bindings.inputs(Action::Sneak).next(), None);
+        assert_eq!(
+            bindings.action(Input::Key(KeyCode::KeyW)),
+            Some(Action::MoveForward)
+        );
+        assert_eq!(reloaded, bindings);
+        assert!(text.contains("Ignite = \n"), "{text}");
+    }
+}
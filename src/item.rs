@@ -0,0 +1,34 @@
+use crate::block::BLOCK_COUNT;
+
+/// Item ids from here up are tools, everything below is the block with the same id.
+pub const FIRST_TOOL: u8 = 128;
+pub const PICKAXE: u8 = FIRST_TOOL;
+pub const SHOVEL: u8 = FIRST_TOOL + 1;
+pub const AXE: u8 = FIRST_TOOL + 2;
+
+/// Breaks the blocks it is meant for faster.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Tool {
+    Pickaxe,
+    Shovel,
+    Axe,
+}
+
+/// Whether the item can be placed as a block.
+pub fn is_block(item: u8) -> bool {
+    item < FIRST_TOOL
+}
+
+/// Whether the item is a defined block or tool.
+pub fn is_known(item: u8) -> bool {
+    item < BLOCK_COUNT || get_tool(item).is_some()
+}
+
+pub fn get_tool(item: u8) -> Option<Tool> {
+    match item {
+        PICKAXE => Some(Tool::Pickaxe),
+        SHOVEL => Some(Tool::Shovel),
+        AXE => Some(Tool::Axe),
+        _ => None,
+    }
+}
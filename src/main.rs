@@ -1,9 +1,14 @@
 use renderer::run;
 mod atlas;
 mod block;
+mod crafting;
+pub mod entity;
+pub mod input;
+mod item;
+mod player;
 mod renderer;
 pub mod terrain;
-mod player;
+mod timestep;
 fn main() {
     pollster::block_on(run());
 }
@@ -1,187 +1,587 @@
-use std::cmp::max;
+use cgmath::{Point3, Vector3};
 
-use cgmath::{
-    num_traits::{abs, Float, Signed},
-    InnerSpace, Point3, Vector3,
-};
+use crate::terrain::{BlockPos, World};
 
-use crate::terrain::World;
+/// How far below the feet ground still counts as under a sneaking player.
+const LEDGE_PROBE: f32 = 0.25;
+/// How far below the feet the block being stood on is looked for.
+const GROUND_PROBE: f32 = 0.1;
+/// Sneaking shortens motion towards an edge in steps this long.
+const LEDGE_STEP: f32 = 0.05;
 
-use super::Player;
+/// Boxes closer than this are touching, not overlapping. Keeps rounding errors from
+/// snagging the player on the blocks it slides along.
+const EPSILON: f32 = 1e-4;
 
-pub fn handle_collision(player: &mut Player, world: &World, dt: f32) {
-    let mut hit_ground = world.block_exists(player.position);
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
 
-    for voxel in three_by_three() {
-        if hit_ground {
-            break;
+impl Aabb {
+    /// Box standing on `feet`, centered on it horizontally.
+    pub fn from_feet(feet: Point3<f32>, width: f32, height: f32) -> Self {
+        Self {
+            min: Point3::new(feet.x - width / 2.0, feet.y, feet.z - width / 2.0),
+            max: Point3::new(feet.x + width / 2.0, feet.y + height, feet.z + width / 2.0),
         }
-        if world.block_exists(player.position + voxel) {
-            let closest_point = closest_point_square(player.position, player.position + voxel);
-            hit_ground = pyth(
-                closest_point.x - player.position.x,
-                closest_point.z - player.position.z,
-            ) < (player.width) / 2.0 - player.velocity.magnitude() - dt;
+    }
+
+    pub fn block(position: BlockPos) -> Self {
+        let min = position.min_corner();
+        Self {
+            min,
+            max: min + Vector3::new(1.0, 1.0, 1.0),
         }
     }
 
-    let head_position = player.position
-        + Vector3 {
-            x: 0.0,
-            y: player.height,
-            z: 0.0,
-        };
-    if hit_ground {
-        player.position.y = player.position.y.ceil();
-        player.velocity.y = 0.0;
+    pub fn feet(&self) -> Point3<f32> {
+        Point3::new(
+            (self.min.x + self.max.x) / 2.0,
+            self.min.y,
+            (self.min.z + self.max.z) / 2.0,
+        )
     }
-    let mut hit_head = world.block_exists(head_position);
 
-    for voxel in three_by_three() {
-        if hit_head {
-            break;
+    pub fn translate(&self, offset: Vector3<f32>) -> Self {
+        Self {
+            min: self.min + offset,
+            max: self.max + offset,
         }
-        if world.block_exists(head_position + voxel) {
-            let closest_point = closest_point_square(head_position, head_position + voxel);
-            hit_head = pyth(
-                closest_point.x - head_position.x,
-                closest_point.z - head_position.z,
-            ) < (player.width) / 2.0 - player.velocity.magnitude() - dt;
+    }
+
+    fn overlaps_on(&self, other: &Aabb, axis: usize) -> bool {
+        self.min[axis] < other.max[axis] - EPSILON && self.max[axis] > other.min[axis] + EPSILON
+    }
+
+    /// The box made larger by `amount` on every side.
+    pub fn grow(&self, amount: Vector3<f32>) -> Self {
+        Self {
+            min: self.min - amount,
+            max: self.max + amount,
         }
     }
 
-    if hit_head {
-        player.position.y = head_position.y.floor() - player.height;
-        player.velocity.y = -0.00000000000000000001;
+    /// The boxes share some volume, touching faces do not count.
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        (0..3).all(|axis| self.overlaps_on(other, axis))
     }
 
-    for height in 0..=player.height as usize {
-        let above = player.position
-            + Vector3 {
-                x: 0.0,
-                y: 0.5 + height as f32,
-                z: 0.0,
-            };
+    /// Every block the box covers, including ones it only touches.
+    fn blocks(&self) -> impl Iterator<Item = BlockPos> {
+        let min = BlockPos::containing(self.min);
+        let max = BlockPos::containing(self.max);
+        (min.x..=max.x).flat_map(move |x| {
+            (min.y..=max.y).flat_map(move |y| (min.z..=max.z).map(move |z| BlockPos::new(x, y, z)))
+        })
+    }
+}
 
-        let mut correction = Vector3 {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-        };
-        for voxel in orthagonal() {
-            if world.block_exists(above + voxel) {
-                let closest_point = closest_point_square(player.position, above + voxel);
-                let diff: Vector3<f32> = Vector3 {
-                    x: closest_point.x - player.position.x,
-                    y: 0.0,
-                    z: closest_point.z - player.position.z,
-                };
-                let new_correction = diff - diff.normalize() * (player.width / 2.0);
-                if diff.magnitude() < player.width / 2.0 {
-                    correction += new_correction;
-                }
-            }
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Collision {
+    /// Where the box ended up.
+    pub aabb: Aabb,
+    /// Axes whose motion was cut short by a block.
+    pub blocked: [bool; 3],
+    /// Downward motion was stopped by a block.
+    pub on_ground: bool,
+}
+
+/// Moves `aabb` by `motion` one axis at a time, vertical first, stopping flush against solid
+/// blocks. Every block between the start and the end is checked, so nothing tunnels.
+///
+/// When walking into something at most `step_height` tall the box is lifted onto it instead,
+/// as long as there is room above. Pass zero to disable stepping, e.g. while airborne.
+pub fn move_and_collide(
+    world: &World,
+    aabb: Aabb,
+    motion: Vector3<f32>,
+    step_height: f32,
+) -> Collision {
+    let collision = sweep(world, aabb, motion);
+    let walked_into_something = collision.blocked[0] || collision.blocked[2];
+    if step_height <= 0.0 || !walked_into_something || motion.y > 0.0 {
+        return collision;
+    }
+
+    // lift as far as the ceiling allows, walk, then settle back down onto whatever is there
+    let lift = clip_axis(world, &aabb, 1, step_height);
+    let lifted = aabb.translate(Vector3::new(0.0, lift, 0.0));
+    let walked = sweep(world, lifted, Vector3::new(motion.x, 0.0, motion.z));
+    let settled = sweep(world, walked.aabb, Vector3::new(0.0, motion.y - lift, 0.0));
+
+    let horizontal_distance = |moved: &Aabb| {
+        let offset = moved.min - aabb.min;
+        offset.x * offset.x + offset.z * offset.z
+    };
+    if horizontal_distance(&settled.aabb) <= horizontal_distance(&collision.aabb) {
+        return collision;
+    }
+    Collision {
+        aabb: settled.aabb,
+        blocked: [walked.blocked[0], settled.blocked[1], walked.blocked[2]],
+        on_ground: settled.on_ground,
+    }
+}
+
+fn sweep(world: &World, aabb: Aabb, motion: Vector3<f32>) -> Collision {
+    let mut aabb = aabb;
+    let mut blocked = [false; 3];
+    for axis in [1, 0, 2] {
+        let wanted = motion[axis];
+        if wanted == 0.0 {
+            continue;
         }
-        player.position += correction;
-        player.velocity += correction;
-
-        correction.x = 0.0;
-        correction.z = 0.0;
-        for voxel in three_by_three() {
-            if world.block_exists(above + voxel) {
-                let closest_point = closest_point_square(player.position, above + voxel);
-                let diff: Vector3<f32> = Vector3 {
-                    x: closest_point.x - player.position.x,
-                    y: 0.0,
-                    z: closest_point.z - player.position.z,
-                };
-                let new_correction = diff - diff.normalize() * (player.width / 2.0);
-                if diff.magnitude() < player.width / 2.0 {
-                    correction += new_correction;
-                }
-            }
+        let allowed = clip_axis(world, &aabb, axis, wanted);
+        blocked[axis] = allowed != wanted;
+        let mut offset = Vector3::new(0.0, 0.0, 0.0);
+        offset[axis] = allowed;
+        aabb = aabb.translate(offset);
+    }
+    Collision {
+        aabb,
+        blocked,
+        on_ground: blocked[1] && motion.y < 0.0,
+    }
+}
+
+/// Shortens the horizontal part of `motion` until the box still has ground under it, so a
+/// sneaking player stops at edges instead of walking off.
+pub fn keep_on_ledge(world: &World, aabb: Aabb, motion: Vector3<f32>) -> Vector3<f32> {
+    let supported =
+        |x: f32, z: f32| collides(world, &aabb.translate(Vector3::new(x, -LEDGE_PROBE, z)));
+    let shrink = |v: f32| {
+        if v.abs() <= LEDGE_STEP {
+            0.0
+        } else {
+            v - LEDGE_STEP * v.signum()
         }
+    };
 
-        player.position += correction;
-        player.velocity += correction;
+    let (mut x, mut z) = (motion.x, motion.z);
+    while x != 0.0 && !supported(x, 0.0) {
+        x = shrink(x);
+    }
+    while z != 0.0 && !supported(0.0, z) {
+        z = shrink(z);
+    }
+    while x != 0.0 && z != 0.0 && !supported(x, z) {
+        x = shrink(x);
+        z = shrink(z);
     }
+    Vector3::new(x, motion.y, z)
 }
-fn closest_point_square(circle_pos: Point3<f32>, square_pos_fix: Point3<f32>) -> Vector3<f32> {
-    let mut test_x = circle_pos.x;
-    let mut test_z = circle_pos.z;
 
-    let square_pos: Point3<f32> = [square_pos_fix.x.floor(), 0.0, square_pos_fix.z.floor()].into();
-    if circle_pos.x < square_pos.x {
-        test_x = square_pos.x;
-    } else if circle_pos.x > square_pos.x + 1.0 {
-        test_x = square_pos.x + 1.0;
+/// The solid block the box stands on, the one under its center if there is one.
+pub fn ground_below(world: &World, aabb: &Aabb) -> Option<BlockPos> {
+    let under_center = BlockPos::containing(aabb.feet() - Vector3::new(0.0, GROUND_PROBE, 0.0));
+    if world.is_solid(under_center) {
+        return Some(under_center);
     }
+    let probe = Aabb {
+        min: aabb.min - Vector3::new(0.0, GROUND_PROBE, 0.0),
+        max: Point3::new(aabb.max.x, aabb.min.y, aabb.max.z),
+    };
+    probe.blocks().find(|position| {
+        let block = Aabb::block(*position);
+        world.is_solid(*position) && (0..3).all(|axis| probe.overlaps_on(&block, axis))
+    })
+}
+
+/// Any fluid block overlaps the box.
+pub fn in_fluid(world: &World, aabb: &Aabb) -> bool {
+    overlapping(aabb, |position| world.is_fluid(position))
+}
 
-    if circle_pos.z < square_pos.z {
-        test_z = square_pos.z;
-    } else if circle_pos.z > square_pos.z + 1.0 {
-        test_z = square_pos.z + 1.0;
+pub fn touching_climbable(world: &World, aabb: &Aabb) -> bool {
+    overlapping(aabb, |position| world.is_climbable(position))
+}
+
+/// Neither solid blocks nor fluids overlap the box.
+pub fn is_free(world: &World, aabb: &Aabb) -> bool {
+    !collides(world, aabb) && !in_fluid(world, aabb)
+}
+
+fn collides(world: &World, aabb: &Aabb) -> bool {
+    overlapping(aabb, |position| world.is_solid(position))
+}
+
+fn overlapping(aabb: &Aabb, matches: impl Fn(BlockPos) -> bool) -> bool {
+    aabb.blocks()
+        .any(|position| matches(position) && aabb.intersects(&Aabb::block(position)))
+}
+
+/// How far `aabb` can move along `axis` before touching a solid block.
+fn clip_axis(world: &World, aabb: &Aabb, axis: usize, motion: f32) -> f32 {
+    let mut offset = Vector3::new(0.0, 0.0, 0.0);
+    offset[axis] = motion;
+    let swept = aabb.translate(offset);
+    let reach = Aabb {
+        min: Point3::new(
+            aabb.min.x.min(swept.min.x),
+            aabb.min.y.min(swept.min.y),
+            aabb.min.z.min(swept.min.z),
+        ),
+        max: Point3::new(
+            aabb.max.x.max(swept.max.x),
+            aabb.max.y.max(swept.max.y),
+            aabb.max.z.max(swept.max.z),
+        ),
+    };
+
+    let mut allowed = motion;
+    for position in reach.blocks() {
+        if !world.is_solid(position) {
+            continue;
+        }
+        let block = Aabb::block(position);
+        let beside = (0..3)
+            .filter(|other| *other != axis)
+            .all(|other| aabb.overlaps_on(&block, other));
+        if !beside {
+            continue;
+        }
+        if motion > 0.0 && block.min[axis] >= aabb.max[axis] - EPSILON {
+            allowed = allowed.min(block.min[axis] - aabb.max[axis]);
+        } else if motion < 0.0 && block.max[axis] <= aabb.min[axis] + EPSILON {
+            allowed = allowed.max(block.max[axis] - aabb.min[axis]);
+        }
     }
-    Vector3 {
-        x: test_x,
-        y: 0.0,
-        z: test_z,
+    // a box that already sits a hair inside the block would get pushed back out, stay put instead
+    if allowed.signum() != motion.signum() {
+        allowed = 0.0;
     }
+    allowed
 }
 
-fn pyth(a: f32, b: f32) -> f32 {
-    (a * a + b * b).sqrt()
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STONE: u8 = 3;
+    const WATER: u8 = 0;
 
-fn three_by_three() -> Vec<Vector3<f32>> {
-    let mut positions = Vec::new();
-    for x in -1..=1 {
-        for z in -1..=1 {
-            if z == 0 && x == 0 {
-                continue;
+    fn floor() -> Vec<([i32; 3], u8)> {
+        let mut blocks = Vec::new();
+        for x in 0..12 {
+            for z in 0..12 {
+                blocks.push(([x, 0, z], STONE));
             }
-            positions.push(Vector3 {
-                x: x as f32,
-                y: 0.0,
-                z: z as f32,
-            })
         }
+        blocks
     }
-    positions
-}
 
-fn orthagonal() -> Vec<Vector3<f32>> {
-    let mut ret = Vec::new();
-
-    for xz in -1..1 {
-        ret.push(Vector3 {
-            x: xz as f32 * 2.0 + 1.0,
-            y: 0.0,
-            z: 0.0,
-        });
-        ret.push(Vector3 {
-            x: 0.0,
-            y: 0.0,
-            z: xz as f32 * 2.0 + 1.0,
-        });
-    }
-    ret
-}
+    fn floor_with_wall(wall: u8) -> World {
+        let mut blocks = floor();
+        for y in 1..=3 {
+            blocks.push(([6, y, 5], wall));
+        }
+        World::with_blocks(&blocks)
+    }
+
+    fn player_box(feet: [f32; 3]) -> Aabb {
+        Aabb::from_feet(feet.into(), 0.6, 1.8)
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{a} != {b}");
+    }
+
+    #[test]
+    fn walking_into_water_is_not_blocked() {
+        let world = floor_with_wall(WATER);
+
+        let collision = move_and_collide(
+            &world,
+            player_box([5.5, 1.0, 5.5]),
+            Vector3::new(1.0, 0.0, 0.0),
+            0.0,
+        );
+
+        assert_eq!(collision.blocked, [false; 3]);
+        assert_close(collision.aabb.feet().x, 6.5);
+    }
+
+    #[test]
+    fn walking_into_a_wall_stops_flush() {
+        let world = floor_with_wall(STONE);
+
+        let collision = move_and_collide(
+            &world,
+            player_box([5.5, 1.0, 5.5]),
+            Vector3::new(1.0, 0.0, 0.0),
+            0.0,
+        );
+
+        assert_eq!(collision.blocked, [true, false, false]);
+        assert_close(collision.aabb.max.x, 6.0);
+    }
+
+    #[test]
+    fn sliding_along_a_wall_keeps_the_parallel_motion() {
+        let mut blocks = floor();
+        for z in 0..12 {
+            blocks.push(([6, 1, z], STONE));
+        }
+        let world = World::with_blocks(&blocks);
+
+        let collision = move_and_collide(
+            &world,
+            player_box([5.5, 1.0, 3.5]),
+            Vector3::new(0.5, 0.0, 2.0),
+            0.0,
+        );
+
+        assert_eq!(collision.blocked, [true, false, false]);
+        assert_close(collision.aabb.max.x, 6.0);
+        assert_close(collision.aabb.feet().z, 5.5);
+    }
+
+    #[test]
+    fn walking_into_a_corner_stops_on_both_axes() {
+        let mut blocks = floor();
+        for i in 0..8 {
+            blocks.push(([6, 1, i], STONE));
+            blocks.push(([i, 1, 6], STONE));
+        }
+        let world = World::with_blocks(&blocks);
+
+        let collision = move_and_collide(
+            &world,
+            player_box([5.0, 1.0, 5.0]),
+            Vector3::new(1.0, 0.0, 1.0),
+            0.0,
+        );
+
+        assert_eq!(collision.blocked, [true, false, true]);
+        assert_close(collision.aabb.max.x, 6.0);
+        assert_close(collision.aabb.max.z, 6.0);
+    }
+
+    #[test]
+    fn touching_a_pillar_does_not_snag() {
+        let world = World::with_blocks(&[([6, 1, 6], STONE)]);
+        // flush against the pillar's -z face while walking past it along x
+        let start = Aabb {
+            min: Point3::new(5.0, 1.0, 5.4),
+            max: Point3::new(5.6, 2.8, 6.0),
+        };
+
+        let collision = move_and_collide(&world, start, Vector3::new(2.0, 0.0, 0.0), 0.0);
+
+        assert_eq!(collision.blocked, [false; 3]);
+        assert_close(collision.aabb.min.x, 7.0);
+    }
+
+    #[test]
+    fn falling_fast_lands_on_a_thin_floor() {
+        let world = World::with_blocks(&[([5, -10, 5], STONE)]);
+
+        let collision = move_and_collide(
+            &world,
+            player_box([5.5, 10.0, 5.5]),
+            Vector3::new(0.0, -100.0, 0.0),
+            0.0,
+        );
+
+        assert!(collision.on_ground);
+        assert_eq!(collision.blocked, [false, true, false]);
+        assert_close(collision.aabb.min.y, -9.0);
+    }
+
+    #[test]
+    fn standing_on_the_ground_stays_grounded() {
+        let world = World::with_blocks(&floor());
+
+        let collision = move_and_collide(
+            &world,
+            player_box([5.5, 1.0, 5.5]),
+            Vector3::new(0.0, -0.01, 0.0),
+            0.0,
+        );
+
+        assert!(collision.on_ground);
+        assert_eq!(collision.aabb.min.y, 1.0);
+    }
+
+    #[test]
+    fn jumping_into_a_ceiling_stops_below_it() {
+        let mut blocks = floor();
+        blocks.push(([5, 3, 5], STONE));
+        let world = World::with_blocks(&blocks);
+
+        let collision = move_and_collide(
+            &world,
+            player_box([5.5, 1.0, 5.5]),
+            Vector3::new(0.0, 0.5, 0.0),
+            0.0,
+        );
+
+        assert!(!collision.on_ground);
+        assert_eq!(collision.blocked, [false, true, false]);
+        assert_close(collision.aabb.max.y, 3.0);
+    }
+
+    #[test]
+    fn water_does_not_hold_the_player_up() {
+        let world = World::with_blocks(&[([5, 1, 5], WATER), ([5, 0, 5], STONE)]);
+
+        let collision = move_and_collide(
+            &world,
+            player_box([5.5, 2.5, 5.5]),
+            Vector3::new(0.0, -2.0, 0.0),
+            0.0,
+        );
+
+        assert!(collision.on_ground);
+        assert_close(collision.aabb.min.y, 1.0);
+    }
+
+    fn stairs(steps: i32) -> World {
+        let mut blocks = floor();
+        for step in 0..steps {
+            for x in 6 + step..12 {
+                for z in 0..12 {
+                    blocks.push(([x, 1 + step, z], STONE));
+                }
+            }
+        }
+        World::with_blocks(&blocks)
+    }
+
+    #[test]
+    fn steps_up_onto_a_one_block_ledge() {
+        let world = stairs(1);
+
+        let collision = move_and_collide(
+            &world,
+            player_box([5.5, 1.0, 5.5]),
+            Vector3::new(0.5, -0.1, 0.0),
+            1.0,
+        );
 
-fn diagonal() -> Vec<Vector3<f32>> {
-    let mut ret = Vec::new();
-
-    for xz in -1..1 {
-        ret.push(Vector3 {
-            x: xz as f32 * 2.0 + 1.0,
-            y: 0.0,
-            z: xz as f32 * 2.0 + 1.0,
-        });
-        ret.push(Vector3 {
-            x: -xz as f32 * 2.0 + 1.0,
-            y: 0.0,
-            z: xz as f32 * 2.0 + 1.0,
-        });
-    }
-    ret
+        assert!(collision.on_ground);
+        assert_eq!(collision.blocked, [false, true, false]);
+        assert_close(collision.aabb.min.y, 2.0);
+        assert_close(collision.aabb.feet().x, 6.0);
+    }
+
+    #[test]
+    fn walks_up_a_staircase() {
+        let world = stairs(3);
+        let mut aabb = player_box([5.0, 1.0, 5.5]);
+
+        for _ in 0..20 {
+            aabb = move_and_collide(&world, aabb, Vector3::new(0.3, -0.1, 0.0), 1.0).aabb;
+        }
+
+        assert_close(aabb.min.y, 4.0);
+        assert!(aabb.min.x > 8.0);
+    }
+
+    #[test]
+    fn does_not_step_up_a_two_block_wall() {
+        let mut blocks = floor();
+        for y in 1..=2 {
+            blocks.push(([6, y, 5], STONE));
+        }
+        let world = World::with_blocks(&blocks);
+
+        let collision = move_and_collide(
+            &world,
+            player_box([5.5, 1.0, 5.5]),
+            Vector3::new(0.5, -0.1, 0.0),
+            1.0,
+        );
+
+        assert_eq!(collision.blocked, [true, true, false]);
+        assert_close(collision.aabb.min.y, 1.0);
+        assert_close(collision.aabb.max.x, 6.0);
+    }
+
+    #[test]
+    fn does_not_step_into_a_ceiling() {
+        // a one block ledge, but the ceiling above it leaves less room than the box is tall
+        let mut blocks = floor();
+        blocks.push(([6, 1, 5], STONE));
+        for x in 5..8 {
+            blocks.push(([x, 3, 5], STONE));
+        }
+        let world = World::with_blocks(&blocks);
+
+        let collision = move_and_collide(
+            &world,
+            player_box([5.5, 1.0, 5.5]),
+            Vector3::new(0.5, -0.1, 0.0),
+            1.0,
+        );
+
+        assert!(collision.blocked[0]);
+        assert_close(collision.aabb.min.y, 1.0);
+        assert_close(collision.aabb.max.y, 2.8);
+        assert_close(collision.aabb.max.x, 6.0);
+    }
+
+    #[test]
+    fn does_not_step_while_rising() {
+        let world = stairs(1);
+
+        let collision = move_and_collide(
+            &world,
+            player_box([5.5, 1.0, 5.5]),
+            Vector3::new(0.5, 0.2, 0.0),
+            1.0,
+        );
+
+        assert!(collision.blocked[0]);
+        assert_close(collision.aabb.min.y, 1.2);
+    }
+
+    #[test]
+    fn ledge_keeps_motion_that_stays_supported() {
+        let world = World::with_blocks(&floor());
+        let motion = Vector3::new(0.5, -0.1, 0.5);
+
+        assert_eq!(
+            keep_on_ledge(&world, player_box([5.5, 1.0, 5.5]), motion),
+            motion
+        );
+    }
+
+    #[test]
+    fn ledge_stops_motion_off_an_edge() {
+        let world = World::with_blocks(&[([5, 0, 5], STONE)]);
+        let start = player_box([5.5, 1.0, 5.5]);
+
+        let motion = keep_on_ledge(&world, start, Vector3::new(2.0, -0.1, 2.0));
+        let end = start.translate(motion);
+
+        // still overhangs the block a little on both axes
+        assert!(end.min.x < 6.0 && end.min.x > 5.5);
+        assert!(end.min.z < 6.0 && end.min.z > 5.5);
+        assert_eq!(motion.y, -0.1);
+    }
+
+    #[test]
+    fn touching_water_from_above_is_not_being_in_it() {
+        let world = World::with_blocks(&[([5, 0, 5], WATER)]);
+
+        assert!(!in_fluid(&world, &player_box([5.5, 1.0, 5.5])));
+        assert!(in_fluid(&world, &player_box([5.5, 0.9, 5.5])));
+        assert!(!is_free(&world, &player_box([5.5, 0.9, 5.5])));
+        assert!(is_free(&world, &player_box([5.5, 1.0, 5.5])));
+    }
+
+    #[test]
+    fn ground_prefers_the_block_under_the_center() {
+        let world = World::with_blocks(&[([5, 0, 5], STONE), ([6, 0, 5], 12)]);
+
+        let centered = player_box([5.9, 1.0, 5.5]);
+        assert_eq!(ground_below(&world, &centered), Some([5, 0, 5].into()));
+        let overhanging = player_box([4.9, 1.0, 5.5]);
+        assert_eq!(ground_below(&world, &overhanging), Some([5, 0, 5].into()));
+        let airborne = player_box([5.5, 1.5, 5.5]);
+        assert_eq!(ground_below(&world, &airborne), None);
+    }
 }
@@ -1,20 +1,50 @@
-use cgmath::{
-    num_traits::{abs, Signed},
-    Angle, InnerSpace, Point3, Rad, Vector3,
-};
-use std::{cmp::min, f32::consts::FRAC_PI_2};
+use cgmath::{Angle, InnerSpace, Rad, Vector3};
+use std::{f32::consts::FRAC_PI_2, mem};
 use winit::{
     dpi::PhysicalPosition,
     event::{ElementState, MouseScrollDelta},
-    keyboard::KeyCode,
 };
 
-use crate::terrain::World;
+use crate::{
+    input::Action,
+    item,
+    terrain::{raycast::RaycastHit, BlockPos, World},
+    timestep::TICK_SECONDS,
+};
 
+use super::inventory::{ItemStack, MAX_STACK_SIZE};
+use super::movement::MovementSpeeds;
+use super::placement::{blocking_entities, check_placement, PlacementError};
 use super::Camera;
 use super::Player;
+use super::{CLIMB_SPEED, FRICTION, JUMP_SPEED, SWIM_ACCELERATION};
 
 const SAFE_FRAC_PI_2: f32 = FRAC_PI_2 - 0.0001;
+/// Speed multipliers while sneaking and sprinting.
+const SNEAK_SPEED: f32 = 0.3;
+const SPRINT_SPEED: f32 = 1.3;
+/// Scrolling this many pixels counts as one line on mice that report pixels.
+const PIXELS_PER_LINE: f32 = 100.0;
+/// Two presses of jump at most this many ticks apart toggle flying.
+const DOUBLE_TAP_TICKS: u32 = 5;
+
+/// A block being broken by holding the break button.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BreakProgress {
+    pub position: BlockPos,
+    block_id: u8,
+    /// Ticks the button has been held on the block.
+    ticks: u32,
+    /// Ticks it takes to break with the tool in hand.
+    needed: u32,
+}
+
+impl BreakProgress {
+    /// How far along breaking is, from 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        (self.ticks as f32 / self.needed.max(1) as f32).min(1.0)
+    }
+}
 
 #[derive(Debug)]
 pub struct PlayerController {
@@ -24,12 +54,24 @@ pub struct PlayerController {
     amount_backward: f32,
     amount_up: f32,
     amount_down: f32,
+    amount_sprint: f32,
     rotate_horizontal: f32,
     rotate_vertical: f32,
+    /// Scrolled lines not turned into hotbar steps yet.
     scroll: f32,
-    speed: f32,
+    pub speeds: MovementSpeeds,
     sensitivity: f32,
-    picked_block: u8,
+    /// Jump presses since the last tick, each one counts towards a double tap.
+    jump_presses: u32,
+    ticks_since_jump_press: Option<u32>,
+    cycle_mode: bool,
+    switch_game_mode: bool,
+    breaking_held: bool,
+    /// The break button went down since the last tick.
+    break_pressed: bool,
+    breaking: Option<BreakProgress>,
+    /// How far away blocks can be broken or placed against.
+    reach: f32,
 }
 
 impl PlayerController {
@@ -41,88 +83,71 @@ impl PlayerController {
             amount_backward: 0.0,
             amount_up: 0.0,
             amount_down: 0.0,
+            amount_sprint: 0.0,
             rotate_horizontal: 0.0,
             rotate_vertical: 0.0,
             scroll: 0.0,
-            speed,
+            speeds: MovementSpeeds {
+                walk: speed,
+                ..Default::default()
+            },
             sensitivity,
-            picked_block: 1,
+            jump_presses: 0,
+            ticks_since_jump_press: None,
+            cycle_mode: false,
+            switch_game_mode: false,
+            breaking_held: false,
+            break_pressed: false,
+            breaking: None,
+            reach: 16.0,
         }
     }
 
-    pub fn process_keyboard(&mut self, key: KeyCode, state: ElementState) -> bool {
+    /// Applies a movement action or holds the break button. Placing, picking and the hotbar
+    /// need the world or the player and are left to the caller.
+    pub fn process_action(&mut self, action: Action, state: ElementState) -> bool {
         let amount = if state == ElementState::Pressed {
             1.0
         } else {
             0.0
         };
-        match key {
-            KeyCode::KeyW | KeyCode::ArrowUp => {
-                self.amount_forward = amount;
-                true
-            }
-            KeyCode::KeyS | KeyCode::ArrowDown => {
-                self.amount_backward = amount;
-                true
-            }
-            KeyCode::KeyA | KeyCode::ArrowLeft => {
-                self.amount_left = amount;
-                true
-            }
-            KeyCode::KeyD | KeyCode::ArrowRight => {
-                self.amount_right = amount;
-                true
-            }
-            KeyCode::Space => {
+        match action {
+            Action::MoveForward => self.amount_forward = amount,
+            Action::MoveBackward => self.amount_backward = amount,
+            Action::MoveLeft => self.amount_left = amount,
+            Action::MoveRight => self.amount_right = amount,
+            Action::Jump => {
+                if amount > self.amount_up {
+                    self.jump_presses += 1;
+                }
                 self.amount_up = amount;
-                true
             }
-            KeyCode::ShiftLeft => {
-                self.amount_down = amount;
-                true
-            }
-            KeyCode::Digit1 => {
-                self.picked_block = 1;
-                true
-            }
-            KeyCode::Digit2 => {
-                self.picked_block = 2;
-                true
-            }
-            KeyCode::Digit3 => {
-                self.picked_block = 3;
-                true
-            }
-            KeyCode::Digit4 => {
-                self.picked_block = 4;
-                true
-            }
-            KeyCode::Digit5 => {
-                self.picked_block = 5;
-                true
-            }
-            KeyCode::Digit6 => {
-                self.picked_block = 6;
-                true
-            }
-            KeyCode::Digit7 => {
-                self.picked_block = 7;
-                true
-            }
-            KeyCode::Digit8 => {
-                self.picked_block = 8;
-                true
+            Action::Sneak => self.amount_down = amount,
+            Action::Sprint => self.amount_sprint = amount,
+            Action::CycleMode => {
+                if state == ElementState::Pressed {
+                    self.cycle_mode = true;
+                }
             }
-            KeyCode::Digit9 => {
-                self.picked_block = 9;
-                true
+            Action::SwitchGameMode => {
+                if state == ElementState::Pressed {
+                    self.switch_game_mode = true;
+                }
             }
-            KeyCode::Digit0 => {
-                self.picked_block = 10;
-                true
+            Action::Break => {
+                let pressed = state == ElementState::Pressed;
+                if pressed && !self.breaking_held {
+                    self.break_pressed = true;
+                }
+                self.breaking_held = pressed;
             }
-            _ => false,
+            Action::Place
+            | Action::PickBlock
+            | Action::Craft
+            | Action::Ignite
+            | Action::HotbarSlot(_) => return false,
         }
+        true
     }
 
     pub fn process_mouse(&mut self, mouse_dx: f64, mouse_dy: f64) {
@@ -131,19 +156,16 @@ impl PlayerController {
     }
 
     pub fn process_scroll(&mut self, delta: &MouseScrollDelta) {
-        self.scroll = -match delta {
-            // I'm assuming a line is about 100 pixels
-            MouseScrollDelta::LineDelta(_, scroll) => scroll * 100.0,
-            MouseScrollDelta::PixelDelta(PhysicalPosition { y: scroll, .. }) => *scroll as f32,
+        self.scroll -= match delta {
+            MouseScrollDelta::LineDelta(_, lines) => *lines,
+            MouseScrollDelta::PixelDelta(PhysicalPosition { y: pixels, .. }) => {
+                *pixels as f32 / PIXELS_PER_LINE
+            }
         };
     }
 
-    fn modulo(a: f32, b: f32) -> f32 {
-        ((a % b) + b) % b
-    }
-    pub fn process_click(&mut self, player: &Player, world: &mut World, place: bool) {
-        let mut current = player.camera.position;
-
+    /// Block under the crosshair within reach.
+    fn target(&self, player: &Player, world: &World) -> Option<RaycastHit> {
         let xz_len = player.camera.pitch.cos();
 
         let direction = Vector3 {
@@ -152,37 +174,142 @@ impl PlayerController {
             z: xz_len * player.camera.yaw.sin(),
         };
 
-        let mut dist = 0.0;
-        loop {
-            let x_dist = (direction.x.is_positive() as u8 as f32 - Self::modulo(current.x, 1.0))
-                / direction.x;
-            let y_dist = (direction.y.is_positive() as u8 as f32 - Self::modulo(current.y, 1.0))
-                / direction.y;
-            let z_dist = (direction.z.is_positive() as u8 as f32 - Self::modulo(current.z, 1.0))
-                / direction.z;
+        world.raycast(player.camera.position, direction, self.reach)
+    }
 
-            let step_size = x_dist.min(y_dist).min(z_dist) + 0.001; // there is a bug that is hidden by this 0.001 where you lag spike when breaking blocks at certain angles
+    /// The block being broken and how far along it is.
+    pub fn breaking(&self) -> Option<BreakProgress> {
+        self.breaking
+    }
 
-            dist += step_size;
-            if dist > 16.0 {
-                break;
+    /// Breaks the block under the crosshair while the break button is held, called once per
+    /// tick. Survival takes longer the harder the block is and starts over on another block,
+    /// creative breaks one block per press.
+    pub fn update_breaking(&mut self, player: &Player, world: &mut World) {
+        let pressed = mem::take(&mut self.break_pressed);
+        let hit = self
+            .target(player, world)
+            .filter(|_| (self.breaking_held || pressed) && player.mode.can_interact());
+        let Some(hit) = hit else {
+            self.breaking = None;
+            return;
+        };
+        if player.game_mode.instant_breaking() {
+            if pressed {
+                Self::break_block(player, world, hit);
             }
+            return;
+        }
 
-            if place {
-                if world.block_exists(current + direction * step_size) {
-                    world.add_block(current, self.picked_block);
-                    break;
-                }
+        let tool = player
+            .inventory
+            .selected_stack()
+            .and_then(|stack| item::get_tool(stack.item));
+        let seconds = hit.block.properties().break_time(tool);
+        let ticks = match self.breaking {
+            Some(progress)
+                if progress.position == hit.position && progress.block_id == hit.block.block_id =>
+            {
+                progress.ticks + 1
             }
-            current += direction * step_size;
+            _ => 1,
+        };
+        let progress = BreakProgress {
+            position: hit.position,
+            block_id: hit.block.block_id,
+            ticks,
+            needed: (seconds / TICK_SECONDS).ceil() as u32,
+        };
+        if progress.ticks >= progress.needed {
+            self.breaking = None;
+            Self::break_block(player, world, hit);
+        } else {
+            self.breaking = Some(progress);
+        }
+    }
 
-            if !place {
-                if world.block_exists(current) {
-                    world.remove_block(current);
-                    break;
-                }
-            }
+    /// Removes the block. Survival drops it as an item to pick up.
+    fn break_block(player: &Player, world: &mut World, hit: RaycastHit) {
+        world.set_block(hit.position, None);
+        if player.game_mode.drops_blocks() {
+            let stack = ItemStack {
+                item: hit.block.block_id,
+                count: 1,
+            };
+            world.drop_item(hit.position, stack);
+        }
+    }
+
+    /// Puts the block under the crosshair in the hand. Survival needs one in the inventory,
+    /// creative makes a stack of it when there is none.
+    pub fn pick_block(&mut self, player: &mut Player, world: &World) -> bool {
+        let Some(hit) = self.target(player, world) else {
+            return false;
+        };
+        let item = hit.block.block_id;
+        if player.inventory.pick(item) {
+            return true;
+        }
+        if player.game_mode.infinite_blocks() {
+            let stack = ItemStack {
+                item,
+                count: MAX_STACK_SIZE,
+            };
+            player
+                .inventory
+                .set_slot(player.inventory.selected(), Some(stack));
+            return true;
+        }
+        false
+    }
+
+    /// Sets off the explosive block under the crosshair and knocks the player back from it.
+    pub fn ignite(&mut self, player: &mut Player, world: &mut World) -> bool {
+        if !player.mode.can_interact() || player.health.is_dead() {
+            return false;
+        }
+        let Some(hit) = self.target(player, world) else {
+            return false;
+        };
+        let Some(blast) = world.detonate(hit.position) else {
+            return false;
+        };
+        player.knock_back(blast.knockback(player.center()));
+        true
+    }
+
+    /// Places the selected block against the face under the crosshair, unless it would end up
+    /// somewhere it does not fit. Uses up one of the stack outside creative.
+    pub fn place_block(
+        &mut self,
+        player: &mut Player,
+        world: &mut World,
+    ) -> Result<(), PlacementError> {
+        if !player.mode.can_interact() {
+            return Err(PlacementError::CannotInteract);
+        }
+        let hit = self
+            .target(player, world)
+            .ok_or(PlacementError::NothingInReach)?;
+        let stack = player
+            .inventory
+            .selected_stack()
+            .ok_or(PlacementError::EmptyHand)?;
+        let target = hit.position + hit.normal;
+        let entities = blocking_entities(world);
+        let block = check_placement(
+            world,
+            target,
+            hit.normal,
+            stack.item,
+            &player.aabb(),
+            &entities,
+        )?;
+        if !player.game_mode.infinite_blocks() {
+            player.inventory.take_selected();
         }
+        world.set_block(target, Some(block));
+        Ok(())
     }
 
     pub fn update_camera(&mut self, camera: &mut Camera, dt: f32) {
@@ -203,31 +330,536 @@ impl PlayerController {
             camera.pitch = Rad(SAFE_FRAC_PI_2);
         }
     }
-    pub fn update_player(&mut self, player: &mut Player, dt: f32) {
-        // Move forward/backward and left/right
+    /// Applies the held movement keys to the player, called once per tick.
+    pub fn update_player(&mut self, player: &mut Player) {
+        if mem::take(&mut self.switch_game_mode) {
+            player.set_game_mode(player.game_mode.next());
+        }
+        if mem::take(&mut self.cycle_mode) && player.game_mode.can_fly() {
+            player.mode = player.mode.next();
+        }
+        for _ in 0..mem::take(&mut self.jump_presses) {
+            if self
+                .ticks_since_jump_press
+                .is_some_and(|ticks| ticks <= DOUBLE_TAP_TICKS)
+            {
+                player.toggle_flying();
+                self.ticks_since_jump_press = None;
+            } else {
+                self.ticks_since_jump_press = Some(0);
+            }
+        }
+        self.ticks_since_jump_press = self.ticks_since_jump_press.map(|ticks| ticks + 1);
+
+        // Shift sneaks while walking and descends otherwise
+        player.sneaking = player.mode.has_gravity() && self.amount_down != 0.0;
+        player.sprinting = self.amount_sprint != 0.0
+            && self.amount_forward > self.amount_backward
+            && !player.sneaking;
+
+        // Move forward/backward and left/right, the player settles at the mode's speed
+        let mut speed = self.speeds.get(player.mode);
+        if let Some(ground) = player.ground {
+            speed *= ground.speed_factor;
+        }
+        if player.sneaking {
+            speed *= SNEAK_SPEED;
+        } else if player.sprinting {
+            speed *= SPRINT_SPEED;
+        }
         let (yaw_sin, yaw_cos) = player.camera.yaw.0.sin_cos();
         let forward = Vector3::new(yaw_cos, 0.0, yaw_sin).normalize();
         let right = Vector3::new(-yaw_sin, 0.0, yaw_cos).normalize();
-        player.velocity += forward * (self.amount_forward - self.amount_backward) * self.speed * dt;
-        player.velocity += right * (self.amount_right - self.amount_left) * self.speed * dt;
-
-        // Move in/out (aka. "zoom")
-        // Note: this isn't an actual zoom. The camera's position
-        // changes when zooming. I've added this to make it easier
-        // to get closer to an object you want to focus on.
-        let (pitch_sin, pitch_cos) = player.camera.pitch.0.sin_cos();
-        let scrollward =
-            Vector3::new(pitch_cos * yaw_cos, pitch_sin, pitch_cos * yaw_sin).normalize();
-        player.position += scrollward * self.scroll * self.speed * self.sensitivity * dt;
-        self.scroll = 0.0;
-
-        // Move up/down. Since we don't use roll, we can just
-        // modify the y coordinate directly.
-        //player.position.y += (self.amount_up - self.amount_down) * self.speed * dt;
-        if player.velocity.y == 0.0 && self.amount_up != 0.0 {
-            player.velocity.y = 0.2;
-        }
-
-        // Physics
+        let acceleration = speed * (1.0 - player.friction());
+        player.velocity += forward * (self.amount_forward - self.amount_backward) * acceleration;
+        player.velocity += right * (self.amount_right - self.amount_left) * acceleration;
+
+        // Scroll through the hotbar, one slot per line
+        let steps = self.scroll.trunc();
+        self.scroll -= steps;
+        player.inventory.scroll(steps as i32);
+
+        // Move up/down, swimming in water, climbing, jumping while walking and straight up or down otherwise
+        if player.in_water {
+            player.velocity.y += self.amount_up * SWIM_ACCELERATION;
+        } else if player.climbing {
+            if self.amount_up != 0.0 || self.amount_forward > self.amount_backward {
+                player.velocity.y = CLIMB_SPEED;
+            } else if player.sneaking {
+                player.velocity.y = 0.0;
+            }
+        } else if player.mode.has_gravity() {
+            if player.on_ground && self.amount_up != 0.0 {
+                player.velocity.y = JUMP_SPEED;
+            }
+        } else {
+            player.velocity.y += (self.amount_up - self.amount_down) * speed * (1.0 - FRICTION);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        block::{self, Block},
+        entity::EntityKind,
+        item::{Tool, PICKAXE},
+        player::{
+            game_mode::GameMode,
+            inventory::{Inventory, ItemStack},
+            movement::MovementMode,
+        },
+    };
+
+    fn looking_at_wall_behind_water() -> (Player, World) {
+        let world = World::with_blocks(&[([3, 1, 0], 0), ([4, 1, 0], 0), ([5, 1, 0], 3)]);
+        // yaw and pitch of zero look straight along +x
+        let player = Player::new([2.5, 1.5, 0.5].into());
+        (player, world)
+    }
+
+    /// Presses break and holds it for `ticks` ticks.
+    fn hold_break(
+        controller: &mut PlayerController,
+        player: &Player,
+        world: &mut World,
+        ticks: u32,
+    ) {
+        controller.process_action(Action::Break, ElementState::Pressed);
+        for _ in 0..ticks {
+            controller.update_breaking(player, world);
+        }
+    }
+
+    /// Ticks it takes to break stone with `tool` in hand.
+    fn stone_ticks(tool: Option<Tool>) -> u32 {
+        let seconds = block::get_properties(3).break_time(tool);
+        (seconds / TICK_SECONDS).ceil() as u32
+    }
+
+    #[test]
+    fn breaking_reaches_through_water() {
+        let (player, mut world) = looking_at_wall_behind_water();
+        let mut controller = PlayerController::new(1.0, 1.0);
+
+        hold_break(&mut controller, &player, &mut world, stone_ticks(None));
+
+        assert_eq!(world.get_block([5, 1, 0].into()), None);
+        assert_eq!(world.get_block([4, 1, 0].into()).unwrap().block_id, 0);
+    }
+
+    #[test]
+    fn placing_replaces_water() {
+        let (mut player, mut world) = looking_at_wall_behind_water();
+        let mut controller = PlayerController::new(1.0, 1.0);
+
+        controller.place_block(&mut player, &mut world).unwrap();
+
+        assert_eq!(world.get_block([4, 1, 0].into()).unwrap().block_id, 1);
+        assert_eq!(world.get_block([3, 1, 0].into()).unwrap().block_id, 0);
+        assert_eq!(world.get_block([5, 1, 0].into()).unwrap().block_id, 3);
+    }
+
+    #[test]
+    fn placing_uses_up_the_selected_stack() {
+        let (mut player, mut world) = looking_at_wall_behind_water();
+        player.inventory = Inventory::empty();
+        player.inventory.add(5, 1);
+        let mut controller = PlayerController::new(1.0, 1.0);
+
+        controller.place_block(&mut player, &mut world).unwrap();
+        world.set_block([4, 1, 0].into(), None);
+
+        assert_eq!(world.get_block([4, 1, 0].into()), None);
+        assert_eq!(player.inventory.selected_stack(), None);
+        assert_eq!(
+            controller.place_block(&mut player, &mut world),
+            Err(PlacementError::EmptyHand)
+        );
+    }
+
+    #[test]
+    fn breaking_drops_the_block() {
+        let (player, mut world) = looking_at_wall_behind_water();
+        let mut controller = PlayerController::new(1.0, 1.0);
+
+        hold_break(&mut controller, &player, &mut world, stone_ticks(None));
+
+        let (_, drop) = world.entities.iter().next().unwrap();
+        assert_eq!(drop.kind, EntityKind::Item(ItemStack { item: 3, count: 1 }));
+        assert_eq!(
+            BlockPos::containing(drop.transform.position),
+            [5, 1, 0].into()
+        );
+    }
+
+    #[test]
+    fn middle_click_picks_the_targeted_block() {
+        let (mut player, mut world) = looking_at_wall_behind_water();
+        let mut controller = PlayerController::new(1.0, 1.0);
+
+        assert!(controller.pick_block(&mut player, &world));
+        assert_eq!(player.inventory.selected_stack().unwrap().item, 3);
+
+        world.set_block(
+            [5, 1, 0].into(),
+            Some(Block {
+                block_id: 12,
+                block_state: 0,
+            }),
+        );
+        player.inventory = Inventory::empty();
+        assert!(!controller.pick_block(&mut player, &world));
+    }
+
+    #[test]
+    fn scrolling_cycles_the_hotbar() {
+        let world = World::with_blocks(&[]);
+        let mut player = Player::new([0.5, 5.0, 0.5].into());
+        let mut controller = PlayerController::new(15.0, 0.2);
+
+        controller.process_scroll(&MouseScrollDelta::LineDelta(0.0, -2.0));
+        player.tick(&mut controller, &world);
+        assert_eq!(player.inventory.selected(), 2);
+
+        // half a line on a touchpad waits for the other half
+        controller.process_scroll(&MouseScrollDelta::PixelDelta((0.0, 50.0).into()));
+        player.tick(&mut controller, &world);
+        assert_eq!(player.inventory.selected(), 2);
+        controller.process_scroll(&MouseScrollDelta::PixelDelta((0.0, 50.0).into()));
+        player.tick(&mut controller, &world);
+        assert_eq!(player.inventory.selected(), 1);
+        // and no longer moves the camera
+        assert_eq!((player.position.x, player.position.z), (0.5, 0.5));
+    }
+
+    #[test]
+    fn ladders_hang_on_the_face_they_are_placed_against() {
+        let (mut player, mut world) = looking_at_wall_behind_water();
+        let mut controller = PlayerController::new(1.0, 1.0);
+        player.inventory.pick(15);
+
+        controller.place_block(&mut player, &mut world).unwrap();
+
+        let ladder = world.get_block([4, 1, 0].into()).unwrap();
+        assert_eq!(ladder.block_id, 15);
+        assert_eq!(block::wall_direction(ladder.block_state), Vector3::unit_x());
+    }
+
+    #[test]
+    fn ladders_cannot_be_placed_on_the_floor() {
+        let mut world = World::with_blocks(&[([0, 0, 0], 3)]);
+        let mut player = Player::new([0.5, 2.0, 0.5].into());
+        player.camera.pitch = Rad(-SAFE_FRAC_PI_2);
+        let mut controller = PlayerController::new(1.0, 1.0);
+        player.inventory.pick(15);
+
+        assert_eq!(
+            controller.place_block(&mut player, &mut world),
+            Err(PlacementError::NeedsWall)
+        );
+
+        assert_eq!(world.get_block([0, 1, 0].into()), None);
+    }
+
+    #[test]
+    fn blocks_cannot_be_placed_inside_the_player() {
+        let mut world = World::with_blocks(&[([0, 0, 0], 3)]);
+        let mut player = Player::new([0.5, 1.0, 0.5].into());
+        player.camera.position = [0.5, 3.0, 0.5].into();
+        player.camera.pitch = Rad(-SAFE_FRAC_PI_2);
+        let mut controller = PlayerController::new(1.0, 1.0);
+
+        assert_eq!(
+            controller.place_block(&mut player, &mut world),
+            Err(PlacementError::OverlapsPlayer)
+        );
+        assert_eq!(world.get_block([0, 1, 0].into()), None);
+    }
+
+    #[test]
+    fn spectators_cannot_place_blocks() {
+        let (mut player, mut world) = looking_at_wall_behind_water();
+        player.mode = MovementMode::Spectator;
+        let mut controller = PlayerController::new(1.0, 1.0);
+
+        assert_eq!(
+            controller.place_block(&mut player, &mut world),
+            Err(PlacementError::CannotInteract)
+        );
+        assert_eq!(world.get_block([4, 1, 0].into()).unwrap().block_id, 0);
+    }
+
+    fn tap_jump(controller: &mut PlayerController) {
+        controller.process_action(Action::Jump, ElementState::Pressed);
+        controller.process_action(Action::Jump, ElementState::Released);
+    }
+
+    #[test]
+    fn double_tapping_jump_toggles_flying() {
+        let world = World::with_blocks(&[]);
+        let mut player = Player::new([0.5, 5.0, 0.5].into());
+        player.set_game_mode(GameMode::Creative);
+        let mut controller = PlayerController::new(15.0, 0.2);
+
+        tap_jump(&mut controller);
+        player.tick(&mut controller, &world);
+        tap_jump(&mut controller);
+        player.tick(&mut controller, &world);
+        assert_eq!(player.mode, MovementMode::Fly);
+
+        tap_jump(&mut controller);
+        player.tick(&mut controller, &world);
+        tap_jump(&mut controller);
+        player.tick(&mut controller, &world);
+        assert_eq!(player.mode, MovementMode::Walk);
+    }
+
+    #[test]
+    fn survival_cannot_fly() {
+        let world = World::with_blocks(&[]);
+        let mut player = Player::new([0.5, 5.0, 0.5].into());
+        let mut controller = PlayerController::new(15.0, 0.2);
+
+        tap_jump(&mut controller);
+        player.tick(&mut controller, &world);
+        tap_jump(&mut controller);
+        controller.process_action(Action::CycleMode, ElementState::Pressed);
+        player.tick(&mut controller, &world);
+
+        assert_eq!(player.mode, MovementMode::Walk);
+    }
+
+    #[test]
+    fn switching_to_survival_lands_the_player() {
+        let world = World::with_blocks(&[]);
+        let mut player = Player::new([0.5, 5.0, 0.5].into());
+        player.set_game_mode(GameMode::Creative);
+        player.mode = MovementMode::Noclip;
+        let mut controller = PlayerController::new(15.0, 0.2);
+
+        controller.process_action(Action::SwitchGameMode, ElementState::Pressed);
+        player.tick(&mut controller, &world);
+
+        assert_eq!(player.game_mode, GameMode::Survival);
+        assert_eq!(player.mode, MovementMode::Walk);
+    }
+
+    #[test]
+    fn creative_places_without_running_out() {
+        let (mut player, mut world) = looking_at_wall_behind_water();
+        player.set_game_mode(GameMode::Creative);
+        player.inventory = Inventory::empty();
+        player.inventory.add(5, 1);
+        let mut controller = PlayerController::new(1.0, 1.0);
+
+        for _ in 0..3 {
+            controller.place_block(&mut player, &mut world).unwrap();
+            world.set_block([4, 1, 0].into(), None);
+        }
+
+        assert_eq!(
+            player.inventory.selected_stack(),
+            Some(ItemStack { item: 5, count: 1 })
+        );
+    }
+
+    #[test]
+    fn creative_breaking_drops_nothing() {
+        let (mut player, mut world) = looking_at_wall_behind_water();
+        player.set_game_mode(GameMode::Creative);
+        let mut controller = PlayerController::new(1.0, 1.0);
+
+        hold_break(&mut controller, &player, &mut world, 1);
+
+        assert_eq!(world.get_block([5, 1, 0].into()), None);
+        assert!(world.entities.is_empty());
+    }
+
+    #[test]
+    fn igniting_explosive_blocks_knocks_the_player_back() {
+        let mut world = World::with_blocks(&[([5, 1, 0], 17), ([6, 1, 0], 2)]);
+        let mut player = Player::new([2.5, 1.5, 0.5].into());
+        let mut controller = PlayerController::new(1.0, 1.0);
+
+        assert!(controller.ignite(&mut player, &mut world));
+
+        assert_eq!(world.get_block([5, 1, 0].into()), None);
+        assert_eq!(world.get_block([6, 1, 0].into()), None);
+        assert!(player.velocity.x < 0.0, "{:?}", player.velocity);
+        assert!(!controller.ignite(&mut player, &mut world));
+    }
+
+    #[test]
+    fn creative_picks_blocks_it_does_not_have() {
+        let (mut player, world) = looking_at_wall_behind_water();
+        player.set_game_mode(GameMode::Creative);
+        player.inventory = Inventory::empty();
+        let mut controller = PlayerController::new(1.0, 1.0);
+
+        assert!(controller.pick_block(&mut player, &world));
+        assert_eq!(
+            player.inventory.selected_stack(),
+            Some(ItemStack {
+                item: 3,
+                count: MAX_STACK_SIZE
+            })
+        );
+    }
+
+    #[test]
+    fn slow_jump_taps_do_not_toggle_flying() {
+        let world = World::with_blocks(&[]);
+        let mut player = Player::new([0.5, 5.0, 0.5].into());
+        let mut controller = PlayerController::new(15.0, 0.2);
+
+        tap_jump(&mut controller);
+        for _ in 0..=DOUBLE_TAP_TICKS {
+            player.tick(&mut controller, &world);
+        }
+        tap_jump(&mut controller);
+        player.tick(&mut controller, &world);
+
+        assert_eq!(player.mode, MovementMode::Walk);
+    }
+
+    #[test]
+    fn flying_holds_height_and_climbs_with_jump() {
+        let world = World::with_blocks(&[]);
+        let mut player = Player::new([0.5, 5.0, 0.5].into());
+        player.mode = MovementMode::Fly;
+        let mut controller = PlayerController::new(15.0, 0.2);
+
+        for _ in 0..20 {
+            player.tick(&mut controller, &world);
+        }
+        assert_eq!(player.position.y, 5.0);
+
+        controller.process_action(Action::Jump, ElementState::Pressed);
+        for _ in 0..20 {
+            player.tick(&mut controller, &world);
+        }
+        assert!(player.position.y > 10.0);
+    }
+
+    #[test]
+    fn noclip_passes_through_blocks_and_fly_does_not() {
+        let world = World::with_blocks(&[([3, 5, 0], 3), ([3, 6, 0], 3), ([3, 7, 0], 3)]);
+        let mut controller = PlayerController::new(15.0, 0.2);
+        controller.process_action(Action::MoveForward, ElementState::Pressed);
+
+        let mut flying = Player::new([0.5, 5.0, 0.5].into());
+        flying.mode = MovementMode::Fly;
+        let mut noclip = Player::new([0.5, 5.0, 0.5].into());
+        noclip.mode = MovementMode::Noclip;
+        for _ in 0..20 {
+            flying.tick(&mut controller, &world);
+            noclip.tick(&mut controller, &world);
+        }
+
+        assert!(flying.position.x < 3.0);
+        assert!(noclip.position.x > 5.0);
+    }
+
+    #[test]
+    fn spectators_cannot_break_blocks() {
+        let (mut player, mut world) = looking_at_wall_behind_water();
+        player.mode = MovementMode::Spectator;
+        let mut controller = PlayerController::new(1.0, 1.0);
+
+        hold_break(&mut controller, &player, &mut world, stone_ticks(None));
+
+        assert_eq!(world.get_block([5, 1, 0].into()).unwrap().block_id, 3);
+    }
+
+    #[test]
+    fn breaking_takes_longer_for_harder_blocks() {
+        let (player, mut world) = looking_at_wall_behind_water();
+        let mut controller = PlayerController::new(1.0, 1.0);
+        let needed = stone_ticks(None);
+
+        hold_break(&mut controller, &player, &mut world, needed - 1);
+
+        assert_eq!(world.get_block([5, 1, 0].into()).unwrap().block_id, 3);
+        let progress = controller.breaking().unwrap();
+        assert_eq!(progress.position, [5, 1, 0].into());
+        assert!(progress.fraction() > 0.9 && progress.fraction() < 1.0);
+
+        controller.update_breaking(&player, &mut world);
+
+        assert_eq!(world.get_block([5, 1, 0].into()), None);
+        assert_eq!(controller.breaking(), None);
+    }
+
+    #[test]
+    fn the_right_tool_breaks_faster() {
+        let (mut player, mut world) = looking_at_wall_behind_water();
+        player.inventory = Inventory::empty();
+        player.inventory.add(PICKAXE, 1);
+        let mut controller = PlayerController::new(1.0, 1.0);
+        let needed = stone_ticks(Some(Tool::Pickaxe));
+        assert!(needed < stone_ticks(None));
+
+        hold_break(&mut controller, &player, &mut world, needed);
+
+        assert_eq!(world.get_block([5, 1, 0].into()), None);
+    }
+
+    #[test]
+    fn releasing_the_button_resets_progress() {
+        let (player, mut world) = looking_at_wall_behind_water();
+        let mut controller = PlayerController::new(1.0, 1.0);
+
+        hold_break(&mut controller, &player, &mut world, 10);
+        controller.process_action(Action::Break, ElementState::Released);
+        controller.update_breaking(&player, &mut world);
+
+        assert_eq!(controller.breaking(), None);
+
+        hold_break(&mut controller, &player, &mut world, stone_ticks(None) - 1);
+
+        assert_eq!(world.get_block([5, 1, 0].into()).unwrap().block_id, 3);
+    }
+
+    #[test]
+    fn looking_at_another_block_starts_over() {
+        let (player, mut world) = looking_at_wall_behind_water();
+        world.set_block([5, 1, 0].into(), None);
+        world.set_block(
+            [4, 1, 0].into(),
+            Some(Block {
+                block_id: 3,
+                block_state: 0,
+            }),
+        );
+        let mut controller = PlayerController::new(1.0, 1.0);
+
+        hold_break(&mut controller, &player, &mut world, 10);
+        world.set_block(
+            [3, 1, 0].into(),
+            Some(Block {
+                block_id: 3,
+                block_state: 0,
+            }),
+        );
+        controller.update_breaking(&player, &mut world);
+
+        let progress = controller.breaking().unwrap();
+        assert_eq!(progress.position, [3, 1, 0].into());
+        assert_eq!(progress.ticks, 1);
+    }
+
+    #[test]
+    fn creative_breaks_one_block_per_press() {
+        let mut world = World::with_blocks(&[([3, 1, 0], 3), ([4, 1, 0], 3)]);
+        let mut player = Player::new([2.5, 1.5, 0.5].into());
+        player.set_game_mode(GameMode::Creative);
+        let mut controller = PlayerController::new(1.0, 1.0);
+
+        hold_break(&mut controller, &player, &mut world, 20);
+
+        assert_eq!(world.get_block([3, 1, 0].into()), None);
+        assert_eq!(world.get_block([4, 1, 0].into()).unwrap().block_id, 3);
     }
 }
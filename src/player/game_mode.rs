@@ -0,0 +1,58 @@
+/// Rules for how the player interacts with the world.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum GameMode {
+    /// Blocks come from and go to the inventory, no flying and health matters.
+    #[default]
+    Survival,
+    /// Every block without running out, instant breaking, flying and no damage except from
+    /// the void.
+    Creative,
+}
+
+impl GameMode {
+    /// Flying, noclip and spectating are allowed, survival only walks.
+    pub fn can_fly(self) -> bool {
+        self == Self::Creative
+    }
+
+    /// Placing does not use up blocks and picking gets any block.
+    pub fn infinite_blocks(self) -> bool {
+        self == Self::Creative
+    }
+
+    /// Blocks break on the first tick instead of taking time by hardness.
+    pub fn instant_breaking(self) -> bool {
+        self == Self::Creative
+    }
+
+    /// Broken blocks drop as items.
+    pub fn drops_blocks(self) -> bool {
+        self == Self::Survival
+    }
+
+    pub fn takes_damage(self) -> bool {
+        self == Self::Survival
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Survival => Self::Creative,
+            Self::Creative => Self::Survival,
+        }
+    }
+
+    pub fn id(self) -> u8 {
+        match self {
+            Self::Survival => 0,
+            Self::Creative => 1,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::Survival),
+            1 => Some(Self::Creative),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DamageCause {
+    Fall,
+    /// Fell out of the bottom of the world.
+    Void,
+    Drowning,
+}
+
+/// Something happened to the player's health. Collected by the player and drained by whoever
+/// shows or sends them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HealthEvent {
+    Damaged { amount: u32, cause: DamageCause },
+    Died { cause: DamageCause },
+    Respawned,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Health {
+    pub fn full(max: u32) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current == 0
+    }
+
+    /// Takes up to `amount` and returns how much was actually lost.
+    pub fn damage(&mut self, amount: u32) -> u32 {
+        let lost = amount.min(self.current);
+        self.current -= lost;
+        lost
+    }
+
+    pub fn restore(&mut self) {
+        self.current = self.max;
+    }
+}
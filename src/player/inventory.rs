@@ -0,0 +1,299 @@
+use crate::{
+    crafting::{CraftingGrid, Recipes},
+    input::HOTBAR_SLOTS,
+    item::{AXE, PICKAXE, SHOVEL},
+};
+
+/// Slots along the bottom of the screen that can be selected and placed from.
+pub const HOTBAR_SIZE: usize = HOTBAR_SLOTS as usize;
+/// Slots of the main grid behind the hotbar.
+pub const GRID_SIZE: usize = 27;
+pub const MAX_STACK_SIZE: u8 = 64;
+/// Blocks a new player gets a full stack of.
+const STARTER_ITEMS: std::ops::RangeInclusive<u8> = 1..=16;
+/// Tools a new player gets one of.
+const STARTER_TOOLS: [u8; 3] = [PICKAXE, SHOVEL, AXE];
+
+/// Some number of one item, items are block ids.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ItemStack {
+    pub item: u8,
+    pub count: u8,
+}
+
+/// The hotbar followed by the main grid, with one hotbar slot selected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inventory {
+    slots: [Option<ItemStack>; HOTBAR_SIZE + GRID_SIZE],
+    selected: usize,
+}
+
+impl Inventory {
+    pub fn empty() -> Self {
+        Self {
+            slots: [None; HOTBAR_SIZE + GRID_SIZE],
+            selected: 0,
+        }
+    }
+
+    /// A stack of every placeable block, filled in from the first hotbar slot, and a set of
+    /// tools after them.
+    pub fn starter() -> Self {
+        let mut inventory = Self::empty();
+        for item in STARTER_ITEMS {
+            inventory.add(item, MAX_STACK_SIZE);
+        }
+        for tool in STARTER_TOOLS {
+            inventory.add(tool, 1);
+        }
+        inventory
+    }
+
+    /// Hotbar slots first, then the grid.
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    pub fn set_slot(&mut self, slot: usize, stack: Option<ItemStack>) {
+        self.slots[slot] = stack.filter(|stack| stack.count > 0);
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_stack(&self) -> Option<ItemStack> {
+        self.slots[self.selected]
+    }
+
+    /// Selects a hotbar slot, anything past the hotbar is ignored.
+    pub fn select(&mut self, slot: usize) {
+        if slot < HOTBAR_SIZE {
+            self.selected = slot;
+        }
+    }
+
+    /// Moves the selection by `steps` hotbar slots, wrapping around at either end.
+    pub fn scroll(&mut self, steps: i32) {
+        self.selected = (self.selected as i32 + steps).rem_euclid(HOTBAR_SIZE as i32) as usize;
+    }
+
+    /// Tops up stacks of `item` before starting new ones, hotbar first. Returns how many did
+    /// not fit.
+    pub fn add(&mut self, item: u8, mut count: u8) -> u8 {
+        for stack in self.slots.iter_mut().flatten() {
+            if stack.item == item {
+                let moved = count.min(MAX_STACK_SIZE - stack.count);
+                stack.count += moved;
+                count -= moved;
+            }
+        }
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if count == 0 {
+                break;
+            }
+            let moved = count.min(MAX_STACK_SIZE);
+            *slot = Some(ItemStack { item, count: moved });
+            count -= moved;
+        }
+        count
+    }
+
+    /// Removes one item from the selected slot and returns it.
+    pub fn take_selected(&mut self) -> Option<u8> {
+        let slot = &mut self.slots[self.selected];
+        let stack = slot.as_mut()?;
+        stack.count -= 1;
+        let item = stack.item;
+        if stack.count == 0 {
+            *slot = None;
+        }
+        Some(item)
+    }
+
+    /// Gets `item` into the selected slot: selects it if it is on the hotbar, otherwise swaps
+    /// it in from the grid. Returns false if the inventory has none.
+    pub fn pick(&mut self, item: u8) -> bool {
+        let holds = |slot: &Option<ItemStack>| slot.is_some_and(|stack| stack.item == item);
+        if holds(&self.slots[self.selected]) {
+            return true;
+        }
+        if let Some(slot) = self.slots[..HOTBAR_SIZE].iter().position(holds) {
+            self.selected = slot;
+            return true;
+        }
+        match self.slots[HOTBAR_SIZE..].iter().position(holds) {
+            Some(slot) => {
+                self.slots.swap(self.selected, HOTBAR_SIZE + slot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Crafts with one of the selected item alone on the grid and adds the result. Returns
+    /// what was made, or None if no recipe matches or the result does not fit.
+    pub fn craft_selected(&mut self, recipes: &Recipes) -> Option<ItemStack> {
+        let mut grid = CraftingGrid::empty();
+        grid.set(0, 0, Some(self.selected_stack()?.item));
+        let result = recipes.find(&grid)?.result;
+
+        let mut crafted = self.clone();
+        crafted.take_selected();
+        if crafted.add(result.item, result.count) > 0 {
+            return None;
+        }
+        *self = crafted;
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack(item: u8, count: u8) -> Option<ItemStack> {
+        Some(ItemStack { item, count })
+    }
+
+    fn count(inventory: &Inventory, item: u8) -> u32 {
+        inventory
+            .slots()
+            .iter()
+            .flatten()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count as u32)
+            .sum()
+    }
+
+    #[test]
+    fn adding_tops_up_existing_stacks_first() {
+        let mut inventory = Inventory::empty();
+        inventory.set_slot(3, stack(2, 60));
+
+        let left = inventory.add(2, 10);
+
+        assert_eq!(left, 0);
+        assert_eq!(inventory.slots()[3], stack(2, MAX_STACK_SIZE));
+        assert_eq!(inventory.slots()[0], stack(2, 6));
+        assert_eq!(count(&inventory, 2), 70);
+    }
+
+    #[test]
+    fn adding_to_a_full_inventory_returns_the_rest() {
+        let mut inventory = Inventory::empty();
+        for slot in 0..HOTBAR_SIZE + GRID_SIZE {
+            inventory.set_slot(slot, stack(3, MAX_STACK_SIZE - 1));
+        }
+
+        assert_eq!(inventory.add(4, 5), 5);
+        assert_eq!(inventory.add(3, 200), 200 - (HOTBAR_SIZE + GRID_SIZE) as u8);
+        assert_eq!(count(&inventory, 4), 0);
+    }
+
+    #[test]
+    fn taking_empties_the_slot_on_the_last_item() {
+        let mut inventory = Inventory::empty();
+        inventory.set_slot(0, stack(5, 2));
+
+        assert_eq!(inventory.take_selected(), Some(5));
+        assert_eq!(inventory.selected_stack(), stack(5, 1));
+        assert_eq!(inventory.take_selected(), Some(5));
+        assert_eq!(inventory.selected_stack(), None);
+        assert_eq!(inventory.take_selected(), None);
+    }
+
+    #[test]
+    fn scrolling_wraps_around_the_hotbar() {
+        let mut inventory = Inventory::empty();
+
+        inventory.scroll(-1);
+        assert_eq!(inventory.selected(), HOTBAR_SIZE - 1);
+        inventory.scroll(2);
+        assert_eq!(inventory.selected(), 1);
+        inventory.scroll(HOTBAR_SIZE as i32 * 3);
+        assert_eq!(inventory.selected(), 1);
+    }
+
+    #[test]
+    fn only_hotbar_slots_can_be_selected() {
+        let mut inventory = Inventory::empty();
+
+        inventory.select(4);
+        inventory.select(HOTBAR_SIZE);
+
+        assert_eq!(inventory.selected(), 4);
+    }
+
+    #[test]
+    fn picking_selects_from_the_hotbar() {
+        let mut inventory = Inventory::empty();
+        inventory.set_slot(6, stack(9, 1));
+
+        assert!(inventory.pick(9));
+        assert_eq!(inventory.selected(), 6);
+    }
+
+    #[test]
+    fn picking_swaps_in_from_the_grid() {
+        let mut inventory = Inventory::empty();
+        inventory.set_slot(2, stack(1, 10));
+        inventory.set_slot(HOTBAR_SIZE + 5, stack(15, 3));
+        inventory.select(2);
+
+        assert!(inventory.pick(15));
+        assert_eq!(inventory.selected(), 2);
+        assert_eq!(inventory.selected_stack(), stack(15, 3));
+        assert_eq!(inventory.slots()[HOTBAR_SIZE + 5], stack(1, 10));
+    }
+
+    #[test]
+    fn picking_a_missing_item_changes_nothing() {
+        let mut inventory = Inventory::starter();
+        let before = inventory.clone();
+
+        assert!(!inventory.pick(0));
+        assert_eq!(inventory, before);
+    }
+
+    #[test]
+    fn starter_fills_the_hotbar_in_order() {
+        let inventory = Inventory::starter();
+
+        assert_eq!(inventory.selected_stack(), stack(1, MAX_STACK_SIZE));
+        for item in STARTER_ITEMS {
+            assert_eq!(count(&inventory, item), MAX_STACK_SIZE as u32);
+        }
+        for tool in STARTER_TOOLS {
+            assert_eq!(count(&inventory, tool), 1);
+        }
+    }
+
+    #[test]
+    fn crafting_turns_a_log_into_planks() {
+        let mut inventory = Inventory::empty();
+        inventory.set_slot(0, stack(10, 2));
+
+        let crafted = inventory.craft_selected(&Recipes::default());
+
+        assert_eq!(crafted, stack(8, 4));
+        assert_eq!(count(&inventory, 10), 1);
+        assert_eq!(count(&inventory, 8), 4);
+    }
+
+    #[test]
+    fn crafting_without_a_recipe_or_room_changes_nothing() {
+        let mut inventory = Inventory::empty();
+        inventory.set_slot(0, stack(3, 1));
+        assert_eq!(inventory.craft_selected(&Recipes::default()), None);
+        assert_eq!(count(&inventory, 3), 1);
+
+        for slot in 0..HOTBAR_SIZE + GRID_SIZE {
+            inventory.set_slot(slot, stack(10, MAX_STACK_SIZE));
+        }
+        let before = inventory.clone();
+
+        assert_eq!(inventory.craft_selected(&Recipes::default()), None);
+        assert_eq!(inventory, before);
+    }
+}
@@ -1,19 +1,39 @@
-mod collision;
+pub(crate) mod collision;
 pub mod controller;
+pub mod game_mode;
+pub mod health;
+pub mod inventory;
+pub mod movement;
+pub mod placement;
+mod save;
+
+use std::mem;
 
 use cgmath::Point3;
 use cgmath::Rad;
 use cgmath::Vector3;
-use instant::Duration;
 
-use crate::terrain::World;
+use crate::{
+    block::Material,
+    terrain::{BlockPos, World},
+    timestep::TICK_SECONDS,
+};
 
-use self::controller::PlayerController;
+use self::{
+    collision::Aabb,
+    controller::PlayerController,
+    game_mode::GameMode,
+    health::{DamageCause, Health, HealthEvent},
+    inventory::Inventory,
+    movement::MovementMode,
+};
 
 pub struct Camera {
     pub position: Point3<f32>,
     pub yaw: Rad<f32>,
     pub pitch: Rad<f32>,
+    /// Multiplies the field of view, eases towards wider while sprinting.
+    pub fov_scale: f32,
 }
 impl Camera {
     pub fn new<V: Into<Point3<f32>>, Y: Into<Rad<f32>>, P: Into<Rad<f32>>>(
@@ -25,22 +45,94 @@ impl Camera {
             position: position.into(),
             yaw: yaw.into(),
             pitch: pitch.into(),
+            fov_scale: 1.0,
         }
     }
 }
 
+/// Blocks per second squared.
+pub const GRAVITY: f32 = 30.0;
+/// Share of velocity kept from one tick to the next when not standing on anything, only
+/// horizontally while walking. Blocks bring their own friction.
+pub const FRICTION: f32 = 0.7;
+/// Landing slower than this on a bouncy block doesn't bounce, so the player comes to rest.
+const MIN_BOUNCE_SPEED: f32 = 2.0;
+/// Upward speed of a jump in blocks per second.
+pub const JUMP_SPEED: f32 = 12.0;
+
+/// Share of velocity kept each tick in water, on top of friction.
+const WATER_DRAG: f32 = 0.8;
+/// Gravity is scaled down by this much in water.
+const WATER_GRAVITY: f32 = 0.2;
+/// Upward speed added each tick while swimming up, in blocks per second.
+pub const SWIM_ACCELERATION: f32 = 1.2;
+/// Swimming into a wall whose top is within this height of the feet hops the player out.
+const WATER_EXIT_HEIGHT: f32 = 1.2;
+const WATER_EXIT_BOOST: f32 = 9.0;
+
+pub const MAX_HEALTH: u32 = 20;
+/// Falling this many blocks is free, every further block costs one health.
+const SAFE_FALL_HEIGHT: f32 = 4.5;
+/// Below this height the player dies and respawns.
+pub const VOID_Y: f32 = -64.0;
+/// Ticks the player can hold its breath.
+pub const MAX_AIR: u32 = 300;
+/// Breath regained per tick above water.
+const AIR_REFILL: u32 = 5;
+/// Once out of air the player takes `DROWNING_DAMAGE` every this many ticks.
+const DROWNING_INTERVAL: u32 = 20;
+const DROWNING_DAMAGE: u32 = 2;
+
+/// Eye height as a share of the player's height.
+const EYE_HEIGHT: f32 = 0.8;
+const SNEAK_EYE_HEIGHT: f32 = 0.7;
+/// Field of view multiplier while sprinting, and how quickly it is reached.
+const SPRINT_FOV_SCALE: f32 = 1.15;
+const FOV_EASE: f32 = 10.0;
+/// Speed up a climbable block, and the fastest the player slides down one.
+pub const CLIMB_SPEED: f32 = 4.0;
+const CLIMB_SINK_SPEED: f32 = 3.0;
+
 pub struct Player {
     pub position: Point3<f32>,
+    /// Position after the tick before the last one, rendering interpolates from here.
+    previous_position: Point3<f32>,
     velocity: Vector3<f32>,
     pub camera: Camera,
     pub height: f32,
     pub width: f32,
+    /// Standing on a solid block, set by the last update.
+    pub on_ground: bool,
+    /// Tallest ledge the player walks up onto without jumping.
+    pub step_height: f32,
+    pub mode: MovementMode,
+    /// Change with `set_game_mode` so the movement mode stays allowed.
+    pub game_mode: GameMode,
+    /// Slower, lower and stops at edges. Set by the controller each tick.
+    pub sneaking: bool,
+    pub sprinting: bool,
+    /// Any part of the player overlaps a fluid, updated at the start of each tick.
+    pub in_water: bool,
+    /// Touching a ladder or vine out of water, updated at the start of each tick.
+    pub climbing: bool,
+    /// Material of the block stood on, `None` in the air, updated at the start of each tick.
+    pub ground: Option<Material>,
+    pub health: Health,
+    /// Breath left in ticks.
+    pub air: u32,
+    /// Ticks spent out of air, drowning damage is dealt every `DROWNING_INTERVAL`.
+    drowning: u32,
+    pub inventory: Inventory,
+    /// Where the player comes back after dying.
+    pub spawn_point: Point3<f32>,
+    events: Vec<HealthEvent>,
 }
 
 impl Player {
     pub fn new(position: Point3<f32>) -> Self {
         Self {
             position,
+            previous_position: position,
             velocity: Vector3 {
                 x: 0.0,
                 y: 0.0,
@@ -49,25 +141,844 @@ impl Player {
             camera: Camera::new(position, Rad(0.0), Rad(0.0)),
             height: 2.8,
             width: 1.4,
+            on_ground: false,
+            step_height: 1.0,
+            mode: MovementMode::Walk,
+            game_mode: GameMode::default(),
+            sneaking: false,
+            sprinting: false,
+            in_water: false,
+            climbing: false,
+            ground: None,
+            health: Health::full(MAX_HEALTH),
+            air: MAX_AIR,
+            drowning: 0,
+            inventory: Inventory::starter(),
+            spawn_point: position,
+            events: Vec::new(),
+        }
+    }
+    /// Advances the player by one fixed tick.
+    pub fn tick(&mut self, controller: &mut PlayerController, world: &World) {
+        self.previous_position = self.position;
+        self.move_in(controller, world);
+        if self.mode.has_gravity() {
+            self.breathe(world);
+            if self.position.y < VOID_Y {
+                self.damage(self.health.current, DamageCause::Void);
+            }
+        }
+    }
+
+    fn move_in(&mut self, controller: &mut PlayerController, world: &World) {
+        self.in_water = self.mode.has_gravity() && collision::in_fluid(world, &self.aabb());
+        self.climbing = self.mode.has_gravity()
+            && !self.in_water
+            && collision::touching_climbable(world, &self.aabb());
+        self.ground = if self.on_ground && self.mode.has_gravity() {
+            material_below(world, &self.aabb())
+        } else {
+            None
+        };
+
+        let friction = self.friction();
+        self.velocity.x *= friction;
+        self.velocity.z *= friction;
+        if self.in_water {
+            self.velocity *= WATER_DRAG;
+            controller.update_player(self);
+            self.velocity.y -= GRAVITY * WATER_GRAVITY * TICK_SECONDS;
+        } else if self.climbing {
+            self.velocity.y = (self.velocity.y - GRAVITY * TICK_SECONDS).max(-CLIMB_SINK_SPEED);
+            controller.update_player(self);
+        } else if self.mode.has_gravity() {
+            controller.update_player(self);
+            self.velocity.y -= GRAVITY * TICK_SECONDS;
+        } else {
+            self.velocity.y *= FRICTION;
+            controller.update_player(self);
+        }
+
+        if !self.mode.collides() {
+            self.position += self.velocity * TICK_SECONDS;
+            self.on_ground = false;
+            return;
+        }
+
+        let step_height = if self.on_ground {
+            self.step_height
+        } else {
+            0.0
+        };
+        let mut motion = self.velocity * TICK_SECONDS;
+        if self.sneaking && self.on_ground {
+            let kept = collision::keep_on_ledge(world, self.aabb(), motion);
+            if kept.x != motion.x {
+                self.velocity.x = 0.0;
+            }
+            if kept.z != motion.z {
+                self.velocity.z = 0.0;
+            }
+            motion = kept;
+        }
+        let collision = collision::move_and_collide(world, self.aabb(), motion, step_height);
+        self.position = collision.aabb.feet();
+        let impact = self.velocity.y;
+        for axis in 0..3 {
+            if collision.blocked[axis] {
+                self.velocity[axis] = 0.0;
+            }
+        }
+        self.on_ground = collision.on_ground;
+
+        if self.on_ground && !self.sneaking && impact < -MIN_BOUNCE_SPEED {
+            let restitution =
+                material_below(world, &collision.aabb).map_or(0.0, |ground| ground.restitution);
+            if restitution > 0.0 {
+                self.velocity.y = -impact * restitution;
+                self.on_ground = false;
+            }
+        }
+        if self.on_ground && !self.in_water && self.mode.has_gravity() {
+            let fallen = impact * impact / (2.0 * GRAVITY);
+            let damage = (fallen - SAFE_FALL_HEIGHT).ceil().max(0.0) as u32;
+            self.damage(damage, DamageCause::Fall);
+        }
+
+        if self.in_water && (collision.blocked[0] || collision.blocked[2]) {
+            let climbed =
+                collision
+                    .aabb
+                    .translate(Vector3::new(motion.x, WATER_EXIT_HEIGHT, motion.z));
+            if collision::is_free(world, &climbed) {
+                self.velocity.y = WATER_EXIT_BOOST;
+            }
+        }
+    }
+
+    fn breathe(&mut self, world: &World) {
+        let eyes = self.position + Vector3::new(0.0, self.height * self.eye_height(), 0.0);
+        if !world.is_fluid(BlockPos::containing(eyes)) {
+            self.air = (self.air + AIR_REFILL).min(MAX_AIR);
+            self.drowning = 0;
+        } else if self.air > 0 {
+            self.air -= 1;
+        } else {
+            self.drowning += 1;
+            if self.drowning >= DROWNING_INTERVAL {
+                self.drowning = 0;
+                self.damage(DROWNING_DAMAGE, DamageCause::Drowning);
+            }
+        }
+    }
+
+    /// Takes health away, dying and respawning when it runs out.
+    pub fn damage(&mut self, amount: u32, cause: DamageCause) {
+        if !self.game_mode.takes_damage() && cause != DamageCause::Void {
+            return;
+        }
+        let amount = self.health.damage(amount);
+        if amount == 0 {
+            return;
+        }
+        self.events.push(HealthEvent::Damaged { amount, cause });
+        if self.health.is_dead() {
+            self.events.push(HealthEvent::Died { cause });
+            self.respawn();
+        }
+    }
+
+    /// Back to the spawn point with full health and breath.
+    pub fn respawn(&mut self) {
+        self.position = self.spawn_point;
+        self.previous_position = self.spawn_point;
+        self.velocity = Vector3::new(0.0, 0.0, 0.0);
+        self.on_ground = false;
+        self.health.restore();
+        self.air = MAX_AIR;
+        self.drowning = 0;
+        self.events.push(HealthEvent::Respawned);
+    }
+
+    /// Adds `velocity` on top of the player's own, like an explosion's knockback. Players that
+    /// cannot interact with the world are left alone.
+    pub fn knock_back(&mut self, velocity: Vector3<f32>) {
+        if self.mode.can_interact() {
+            self.velocity += velocity;
+        }
+    }
+
+    /// Middle of the player's box.
+    pub fn center(&self) -> Point3<f32> {
+        self.position + Vector3::new(0.0, self.height / 2.0, 0.0)
+    }
+
+    /// Health events since the last call, oldest first.
+    pub fn take_events(&mut self) -> Vec<HealthEvent> {
+        mem::take(&mut self.events)
+    }
+
+    /// Share of horizontal velocity kept this tick, depends on what the player stands on.
+    pub fn friction(&self) -> f32 {
+        self.ground.map_or(FRICTION, |ground| ground.friction)
+    }
+
+    /// The camera is inside a fluid block, rendering tints the fog.
+    pub fn eyes_in_fluid(&self, world: &World) -> bool {
+        world.is_fluid(BlockPos::containing(self.camera.position))
+    }
+
+    /// Switches between walking and flying if the game mode allows flight, the other movement
+    /// modes are left alone.
+    pub fn toggle_flying(&mut self) {
+        if !self.game_mode.can_fly() {
+            return;
+        }
+        match self.mode {
+            MovementMode::Walk => {
+                self.mode = MovementMode::Fly;
+                self.velocity.y = 0.0;
+            }
+            MovementMode::Fly => self.mode = MovementMode::Walk,
+            MovementMode::Noclip | MovementMode::Spectator => {}
         }
     }
-    pub fn update(&mut self, controller: &mut PlayerController, dt: Duration, world: &World) {
-        let dt = dt.as_secs_f32();
-        controller.update_player(self, dt);
 
-        self.velocity.y -= 0.5 * dt;
-        self.velocity.x *= 0.9;
-        self.velocity.z *= 0.9;
-        self.position += self.velocity;
+    /// Switches the game mode, landing the player if the new one cannot fly.
+    pub fn set_game_mode(&mut self, game_mode: GameMode) {
+        self.game_mode = game_mode;
+        if !game_mode.can_fly() {
+            self.mode = MovementMode::Walk;
+        }
+    }
 
-        collision::handle_collision(self, world, dt);
-        self.camera.position += (self.position
+    /// Puts the camera `alpha` of the way from the previous tick's position to the current one
+    /// and applies mouse look, which is not tied to ticks.
+    pub fn update_camera(&mut self, controller: &mut PlayerController, dt: f32, alpha: f32) {
+        let feet = self.previous_position + (self.position - self.previous_position) * alpha;
+        self.camera.position = feet
             + Vector3 {
                 x: 0.0,
-                y: self.height * 0.8,
+                y: self.height * self.eye_height(),
                 z: 0.0,
-            })
-            - self.camera.position;
+            };
+
+        let fov_scale = if self.sprinting {
+            SPRINT_FOV_SCALE
+        } else {
+            1.0
+        };
+        self.camera.fov_scale += (fov_scale - self.camera.fov_scale) * (FOV_EASE * dt).min(1.0);
         controller.update_camera(&mut self.camera, dt);
     }
+
+    fn eye_height(&self) -> f32 {
+        if self.sneaking {
+            SNEAK_EYE_HEIGHT
+        } else {
+            EYE_HEIGHT
+        }
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Aabb::from_feet(self.position, self.width, self.height)
+    }
+}
+
+fn material_below(world: &World, aabb: &Aabb) -> Option<Material> {
+    let position = collision::ground_below(world, aabb)?;
+    Some(world.get_block(position)?.properties().material)
+}
+
+#[cfg(test)]
+mod tests {
+    use instant::Duration;
+    use winit::event::ElementState;
+
+    use super::*;
+    use crate::{input::Action, timestep::FixedTimestep};
+
+    fn run_at(fps: u32, seconds: u32) -> (Point3<f32>, Vector3<f32>) {
+        let mut blocks = Vec::new();
+        for x in -16..32 {
+            for z in -16..32 {
+                blocks.push(([x, 0, z], 3));
+            }
+        }
+        blocks.push(([8, 1, 1], 3));
+        let world = World::with_blocks(&blocks);
+        let mut player = Player::new([0.5, 4.0, 0.5].into());
+        let mut controller = PlayerController::new(15.0, 0.2);
+        controller.process_action(Action::MoveForward, ElementState::Pressed);
+        controller.process_action(Action::Jump, ElementState::Pressed);
+
+        let mut timestep = FixedTimestep::new();
+        let frame = Duration::from_secs(1) / fps;
+        for _ in 0..fps * seconds {
+            for _ in 0..timestep.advance(frame) {
+                player.tick(&mut controller, &world);
+            }
+            player.update_camera(&mut controller, frame.as_secs_f32(), timestep.alpha());
+        }
+        (player.position, player.velocity)
+    }
+
+    #[test]
+    fn movement_does_not_depend_on_frame_rate() {
+        let slow = run_at(30, 3);
+        let fast = run_at(240, 3);
+
+        assert_eq!(slow, fast);
+        // walked forward and hopped along the way
+        assert!(slow.0.x > 5.0);
+    }
+
+    #[test]
+    fn camera_interpolates_between_ticks() {
+        let world = World::with_blocks(&[]);
+        let mut player = Player::new([0.5, 10.0, 0.5].into());
+        let mut controller = PlayerController::new(15.0, 0.2);
+        player.tick(&mut controller, &world);
+        let eye = player.height * EYE_HEIGHT;
+
+        player.update_camera(&mut controller, 0.0, 0.0);
+        assert_eq!(player.camera.position.y, 10.0 + eye);
+        player.update_camera(&mut controller, 0.0, 1.0);
+        assert_eq!(player.camera.position.y, player.position.y + eye);
+        player.update_camera(&mut controller, 0.0, 0.5);
+        assert!(player.camera.position.y < 10.0 + eye);
+        assert!(player.camera.position.y > player.position.y + eye);
+    }
+
+    fn on_pillar() -> (Player, World) {
+        let mut blocks = Vec::new();
+        for x in -16..32 {
+            for z in -16..32 {
+                blocks.push(([x, 0, z], 3));
+            }
+        }
+        blocks.push(([5, 1, 5], 3));
+        let world = World::with_blocks(&blocks);
+        let mut player = Player::new([5.5, 2.0, 5.5].into());
+        player.on_ground = true;
+        (player, world)
+    }
+
+    #[test]
+    fn sneaking_player_cannot_walk_off_a_pillar() {
+        let (mut player, world) = on_pillar();
+        let mut controller = PlayerController::new(15.0, 0.2);
+        player.camera.yaw = Rad(0.3);
+        controller.process_action(Action::Sneak, ElementState::Pressed);
+        controller.process_action(Action::MoveForward, ElementState::Pressed);
+
+        for _ in 0..40 {
+            player.tick(&mut controller, &world);
+        }
+
+        assert!(player.on_ground);
+        assert_eq!(player.position.y, 2.0);
+        assert!(player.position.x > 5.5);
+    }
+
+    #[test]
+    fn walking_player_falls_off_a_pillar() {
+        let (mut player, world) = on_pillar();
+        let mut controller = PlayerController::new(15.0, 0.2);
+        controller.process_action(Action::MoveForward, ElementState::Pressed);
+
+        for _ in 0..10 {
+            player.tick(&mut controller, &world);
+        }
+
+        assert_eq!(player.position.y, 1.0);
+    }
+
+    #[test]
+    fn sprinting_is_faster_than_walking_and_sneaking_slower() {
+        let floor: Vec<([i32; 3], u8)> = (-2..30)
+            .flat_map(|x| (-2..=2).map(move |z| ([x, 0, z], 3)))
+            .collect();
+        let distance = |mode: MovementMode, action: Option<Action>| {
+            let world = World::with_blocks(&floor);
+            let mut player = Player::new([0.5, 1.0, 0.5].into());
+            player.mode = mode;
+            player.on_ground = true;
+            let mut controller = PlayerController::new(15.0, 0.2);
+            controller.process_action(Action::MoveForward, ElementState::Pressed);
+            if let Some(action) = action {
+                controller.process_action(action, ElementState::Pressed);
+            }
+            for _ in 0..10 {
+                player.tick(&mut controller, &world);
+            }
+            player.position.x
+        };
+
+        let walked = distance(MovementMode::Walk, None);
+        assert!(distance(MovementMode::Walk, Some(Action::Sprint)) > walked);
+        assert!(distance(MovementMode::Walk, Some(Action::Sneak)) < walked);
+        assert!(
+            distance(MovementMode::Fly, Some(Action::Sprint)) > distance(MovementMode::Fly, None)
+        );
+    }
+
+    #[test]
+    fn sprinting_widens_the_view_and_sneaking_lowers_it() {
+        let (mut player, world) = on_pillar();
+        let mut controller = PlayerController::new(15.0, 0.2);
+        controller.process_action(Action::MoveForward, ElementState::Pressed);
+        controller.process_action(Action::Sprint, ElementState::Pressed);
+        player.tick(&mut controller, &world);
+        player.update_camera(&mut controller, 1.0, 1.0);
+        assert_eq!(player.camera.fov_scale, SPRINT_FOV_SCALE);
+
+        let standing = player.camera.position.y - player.position.y;
+        controller.process_action(Action::Sneak, ElementState::Pressed);
+        player.tick(&mut controller, &world);
+        player.update_camera(&mut controller, 1.0, 1.0);
+        assert!(!player.sprinting);
+        assert!(player.camera.position.y - player.position.y < standing);
+    }
+
+    fn pool() -> World {
+        let mut blocks = Vec::new();
+        for x in 0..30 {
+            for z in 0..8 {
+                blocks.push(([x, 0, z], 3));
+                for y in 1..=2 {
+                    let block = if x < 6 { 0 } else { 3 };
+                    blocks.push(([x, y, z], block));
+                }
+            }
+        }
+        World::with_blocks(&blocks)
+    }
+
+    fn deep_water() -> World {
+        let mut blocks = Vec::new();
+        for x in 0..4 {
+            for z in 0..4 {
+                for y in 0..12 {
+                    blocks.push(([x, y, z], 0));
+                }
+            }
+        }
+        World::with_blocks(&blocks)
+    }
+
+    #[test]
+    fn water_slows_falling() {
+        let mut controller = PlayerController::new(15.0, 0.2);
+        let water = deep_water();
+        let air = World::with_blocks(&[]);
+        let mut swimming = Player::new([2.0, 8.0, 2.0].into());
+        let mut falling = Player::new([2.0, 8.0, 2.0].into());
+
+        for _ in 0..10 {
+            swimming.tick(&mut controller, &water);
+            falling.tick(&mut controller, &air);
+        }
+
+        assert!(swimming.in_water);
+        assert!(!falling.in_water);
+        assert!(swimming.position.y < 8.0);
+        assert!(swimming.position.y > 7.0);
+        assert!(falling.position.y < 5.0);
+    }
+
+    #[test]
+    fn holding_jump_swims_up() {
+        let world = deep_water();
+        let mut player = Player::new([2.0, 2.0, 2.0].into());
+        let mut controller = PlayerController::new(15.0, 0.2);
+        controller.process_action(Action::Jump, ElementState::Pressed);
+
+        for _ in 0..10 {
+            player.tick(&mut controller, &world);
+        }
+
+        assert!(player.position.y > 3.0);
+    }
+
+    #[test]
+    fn swimming_into_a_low_edge_climbs_out() {
+        let world = pool();
+        let mut player = Player::new([3.0, 1.0, 4.0].into());
+        let mut controller = PlayerController::new(15.0, 0.2);
+        controller.process_action(Action::MoveForward, ElementState::Pressed);
+        controller.process_action(Action::Jump, ElementState::Pressed);
+
+        for _ in 0..40 {
+            player.tick(&mut controller, &world);
+        }
+        controller.process_action(Action::MoveForward, ElementState::Released);
+        controller.process_action(Action::Jump, ElementState::Released);
+        for _ in 0..40 {
+            player.tick(&mut controller, &world);
+        }
+
+        assert!(player.position.x > 6.0);
+        assert_eq!(player.position.y, 3.0);
+        assert!(!player.in_water);
+    }
+
+    #[test]
+    fn no_boost_against_a_wall_that_reaches_above_the_water() {
+        let mut blocks = vec![];
+        for y in 0..12 {
+            blocks.push(([4, y, 2], 3));
+            for x in 0..4 {
+                blocks.push(([x, y, 2], 0));
+            }
+        }
+        let world = World::with_blocks(&blocks);
+        let mut player = Player::new([2.0, 2.0, 2.5].into());
+        let mut controller = PlayerController::new(15.0, 0.2);
+        controller.process_action(Action::MoveForward, ElementState::Pressed);
+
+        for _ in 0..20 {
+            player.tick(&mut controller, &world);
+            assert!(player.velocity.y <= 0.0);
+        }
+    }
+
+    #[test]
+    fn camera_under_water_is_detected() {
+        let world = deep_water();
+        let mut controller = PlayerController::new(15.0, 0.2);
+        let mut player = Player::new([2.0, 4.0, 2.0].into());
+        player.update_camera(&mut controller, 0.0, 1.0);
+        assert!(player.eyes_in_fluid(&world));
+
+        player.position.y = 11.0;
+        player.previous_position.y = 11.0;
+        player.update_camera(&mut controller, 0.0, 1.0);
+        assert!(!player.eyes_in_fluid(&world));
+    }
+
+    fn floor_of(block_id: u8) -> World {
+        let mut blocks = Vec::new();
+        for x in -16..32 {
+            for z in -16..32 {
+                blocks.push(([x, 0, z], block_id));
+            }
+        }
+        World::with_blocks(&blocks)
+    }
+
+    /// Walks along +x for a second, lets go and returns where the player came to rest.
+    fn walk_and_stop(world: &World) -> (f32, f32) {
+        let mut player = Player::new([0.5, 1.0, 0.5].into());
+        player.on_ground = true;
+        let mut controller = PlayerController::new(15.0, 0.2);
+        controller.process_action(Action::MoveForward, ElementState::Pressed);
+        for _ in 0..10 {
+            player.tick(&mut controller, world);
+        }
+        let walked = player.position.x;
+        controller.process_action(Action::MoveForward, ElementState::Released);
+        for _ in 0..40 {
+            player.tick(&mut controller, world);
+        }
+        (walked, player.position.x - walked)
+    }
+
+    #[test]
+    fn ice_is_slippery() {
+        let (_, stone_slide) = walk_and_stop(&floor_of(3));
+        let (_, ice_slide) = walk_and_stop(&floor_of(12));
+
+        assert!(stone_slide < 2.0);
+        assert!(ice_slide > 2.0 * stone_slide);
+    }
+
+    #[test]
+    fn soul_sand_slows_walking() {
+        let (stone_walk, _) = walk_and_stop(&floor_of(3));
+        let (soul_sand_walk, _) = walk_and_stop(&floor_of(14));
+
+        assert!(soul_sand_walk < 0.5 * stone_walk);
+    }
+
+    #[test]
+    fn slime_bounces_and_settles() {
+        let world = floor_of(13);
+        let mut player = Player::new([0.5, 10.0, 0.5].into());
+        let mut controller = PlayerController::new(15.0, 0.2);
+
+        let mut bounced = false;
+        for _ in 0..30 {
+            player.tick(&mut controller, &world);
+            bounced |= player.velocity.y > 0.0;
+        }
+        assert!(bounced);
+        for _ in 0..200 {
+            player.tick(&mut controller, &world);
+        }
+        assert!(player.on_ground);
+        assert_eq!(player.position.y, 1.0);
+    }
+
+    #[test]
+    fn stone_does_not_bounce() {
+        let world = floor_of(3);
+        let mut player = Player::new([0.5, 10.0, 0.5].into());
+        let mut controller = PlayerController::new(15.0, 0.2);
+
+        for _ in 0..30 {
+            player.tick(&mut controller, &world);
+            assert!(player.velocity.y <= 0.0);
+        }
+        assert!(player.on_ground);
+    }
+
+    fn drop_onto_stone(height: f32) -> Player {
+        let world = floor_of(3);
+        let mut player = Player::new([0.5, 1.0 + height, 0.5].into());
+        let mut controller = PlayerController::new(15.0, 0.2);
+        for _ in 0..60 {
+            player.tick(&mut controller, &world);
+        }
+        assert!(player.on_ground);
+        player
+    }
+
+    #[test]
+    fn short_falls_do_no_damage() {
+        let mut player = drop_onto_stone(4.0);
+
+        assert_eq!(player.health.current, MAX_HEALTH);
+        assert!(player.take_events().is_empty());
+    }
+
+    #[test]
+    fn long_falls_hurt_by_height() {
+        let mut player = drop_onto_stone(10.0);
+        let events = player.take_events();
+
+        let [HealthEvent::Damaged {
+            amount,
+            cause: DamageCause::Fall,
+        }] = events[..]
+        else {
+            panic!("unexpected events {events:?}");
+        };
+        assert!((5..=7).contains(&amount), "{amount}");
+        assert_eq!(player.health.current, MAX_HEALTH - amount);
+    }
+
+    #[test]
+    fn lethal_fall_respawns_at_the_spawn_point() {
+        let world = floor_of(3);
+        let mut player = Player::new([0.5, 41.0, 0.5].into());
+        player.spawn_point = [0.5, 1.0, 0.5].into();
+        let mut controller = PlayerController::new(15.0, 0.2);
+        for _ in 0..60 {
+            player.tick(&mut controller, &world);
+        }
+
+        let events = player.take_events();
+        assert!(matches!(
+            events[..],
+            [
+                HealthEvent::Damaged {
+                    cause: DamageCause::Fall,
+                    ..
+                },
+                HealthEvent::Died {
+                    cause: DamageCause::Fall
+                },
+                HealthEvent::Respawned,
+            ]
+        ));
+        assert_eq!(player.health.current, MAX_HEALTH);
+        assert_eq!(player.position, [0.5, 1.0, 0.5].into());
+    }
+
+    #[test]
+    fn landing_in_water_does_no_damage() {
+        let world = deep_water();
+        let mut player = Player::new([2.0, 40.0, 2.0].into());
+        let mut controller = PlayerController::new(15.0, 0.2);
+
+        for _ in 0..100 {
+            player.tick(&mut controller, &world);
+        }
+
+        assert!(player.take_events().is_empty());
+    }
+
+    #[test]
+    fn creative_takes_no_damage_except_from_the_void() {
+        let mut player = Player::new([0.5, 1.0, 0.5].into());
+        player.set_game_mode(GameMode::Creative);
+
+        player.damage(30, DamageCause::Fall);
+        player.damage(30, DamageCause::Drowning);
+        assert_eq!(player.health.current, MAX_HEALTH);
+        assert!(player.take_events().is_empty());
+
+        player.damage(MAX_HEALTH, DamageCause::Void);
+        assert!(player.take_events().contains(&HealthEvent::Died {
+            cause: DamageCause::Void
+        }));
+    }
+
+    #[test]
+    fn falling_into_the_void_kills() {
+        let world = World::with_blocks(&[]);
+        let mut player = Player::new([0.5, 5.0, 0.5].into());
+        player.spawn_point = [3.5, 7.0, 3.5].into();
+        let mut controller = PlayerController::new(15.0, 0.2);
+
+        let mut events = Vec::new();
+        for _ in 0..100 {
+            player.tick(&mut controller, &world);
+            events.extend(player.take_events());
+            if events.contains(&HealthEvent::Respawned) {
+                break;
+            }
+        }
+
+        assert_eq!(
+            events,
+            [
+                HealthEvent::Damaged {
+                    amount: MAX_HEALTH,
+                    cause: DamageCause::Void
+                },
+                HealthEvent::Died {
+                    cause: DamageCause::Void
+                },
+                HealthEvent::Respawned,
+            ]
+        );
+        assert_eq!(player.position, [3.5, 7.0, 3.5].into());
+    }
+
+    #[test]
+    fn holding_breath_too_long_drowns() {
+        let mut blocks = Vec::new();
+        for y in 0..8 {
+            blocks.push(([0, y, 0], 0));
+        }
+        blocks.push(([0, -1, 0], 3));
+        let world = World::with_blocks(&blocks);
+        let mut player = Player::new([0.5, 0.0, 0.5].into());
+        let mut controller = PlayerController::new(15.0, 0.2);
+
+        for _ in 0..MAX_AIR {
+            player.tick(&mut controller, &world);
+        }
+        assert_eq!(player.air, 0);
+        assert!(player.take_events().is_empty());
+
+        for _ in 0..DROWNING_INTERVAL * 3 {
+            player.tick(&mut controller, &world);
+        }
+        assert_eq!(
+            player.take_events(),
+            [HealthEvent::Damaged {
+                amount: DROWNING_DAMAGE,
+                cause: DamageCause::Drowning
+            }; 3]
+        );
+    }
+
+    #[test]
+    fn breath_comes_back_above_water() {
+        let world = World::with_blocks(&[([0, 0, 0], 3)]);
+        let mut player = Player::new([0.5, 1.0, 0.5].into());
+        player.air = 0;
+        let mut controller = PlayerController::new(15.0, 0.2);
+
+        for _ in 0..MAX_AIR / AIR_REFILL {
+            player.tick(&mut controller, &world);
+        }
+
+        assert_eq!(player.air, MAX_AIR);
+    }
+
+    /// A ladder up a wall at x = 3 and a player standing against it.
+    fn ladder() -> (Player, World) {
+        let mut blocks = vec![([2, 0, 0], 3)];
+        for y in 1..12 {
+            blocks.push(([3, y, 0], 3));
+            blocks.push(([2, y, 0], 15));
+        }
+        let world = World::with_blocks(&blocks);
+        let player = Player::new([2.2, 1.0, 0.5].into());
+        (player, world)
+    }
+
+    #[test]
+    fn holding_jump_climbs_a_ladder() {
+        let (mut player, world) = ladder();
+        let mut controller = PlayerController::new(15.0, 0.2);
+        controller.process_action(Action::Jump, ElementState::Pressed);
+
+        for _ in 0..20 {
+            player.tick(&mut controller, &world);
+        }
+
+        assert!(player.climbing);
+        assert!(player.position.y > 4.0, "{:?}", player.position);
+    }
+
+    #[test]
+    fn walking_into_a_ladder_climbs_it() {
+        let (mut player, world) = ladder();
+        let mut controller = PlayerController::new(15.0, 0.2);
+        controller.process_action(Action::MoveForward, ElementState::Pressed);
+
+        for _ in 0..20 {
+            player.tick(&mut controller, &world);
+        }
+
+        assert!(player.position.y > 4.0, "{:?}", player.position);
+    }
+
+    #[test]
+    fn letting_go_of_a_ladder_sinks_slowly() {
+        let (mut player, world) = ladder();
+        player.position.y = 8.0;
+        let mut controller = PlayerController::new(15.0, 0.2);
+
+        for _ in 0..10 {
+            player.tick(&mut controller, &world);
+        }
+
+        assert!(player.position.y < 8.0);
+        assert!(player.position.y >= 8.0 - CLIMB_SINK_SPEED * 10.0 * TICK_SECONDS);
+        assert_eq!(player.velocity.y, -CLIMB_SINK_SPEED);
+    }
+
+    #[test]
+    fn sneaking_holds_on_to_a_ladder() {
+        let (mut player, world) = ladder();
+        player.position.y = 8.0;
+        let mut controller = PlayerController::new(15.0, 0.2);
+        controller.process_action(Action::Sneak, ElementState::Pressed);
+
+        for _ in 0..10 {
+            player.tick(&mut controller, &world);
+        }
+
+        assert_eq!(player.position.y, 8.0);
+    }
+
+    #[test]
+    fn sliding_down_a_ladder_does_no_damage() {
+        let (mut player, world) = ladder();
+        player.position.y = 11.0;
+        let mut controller = PlayerController::new(15.0, 0.2);
+
+        for _ in 0..100 {
+            player.tick(&mut controller, &world);
+        }
+
+        assert!(player.on_ground);
+        assert_eq!(player.health.current, MAX_HEALTH);
+    }
 }
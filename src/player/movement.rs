@@ -0,0 +1,87 @@
+/// How the player moves through the world.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum MovementMode {
+    /// Gravity, jumping and collision.
+    #[default]
+    Walk,
+    /// No gravity, Space and Shift move up and down.
+    Fly,
+    /// Fly without collision.
+    Noclip,
+    /// Noclip that cannot break or place blocks.
+    Spectator,
+}
+
+impl MovementMode {
+    pub fn has_gravity(self) -> bool {
+        self == Self::Walk
+    }
+
+    pub fn collides(self) -> bool {
+        matches!(self, Self::Walk | Self::Fly)
+    }
+
+    pub fn can_interact(self) -> bool {
+        self != Self::Spectator
+    }
+
+    /// The mode after this one when cycling through all of them.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Walk => Self::Fly,
+            Self::Fly => Self::Noclip,
+            Self::Noclip => Self::Spectator,
+            Self::Spectator => Self::Walk,
+        }
+    }
+
+    pub fn id(self) -> u8 {
+        match self {
+            Self::Walk => 0,
+            Self::Fly => 1,
+            Self::Noclip => 2,
+            Self::Spectator => 3,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::Walk),
+            1 => Some(Self::Fly),
+            2 => Some(Self::Noclip),
+            3 => Some(Self::Spectator),
+            _ => None,
+        }
+    }
+}
+
+/// Top speed in blocks per second for each mode.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MovementSpeeds {
+    pub walk: f32,
+    pub fly: f32,
+    pub noclip: f32,
+    pub spectator: f32,
+}
+
+impl MovementSpeeds {
+    pub fn get(&self, mode: MovementMode) -> f32 {
+        match mode {
+            MovementMode::Walk => self.walk,
+            MovementMode::Fly => self.fly,
+            MovementMode::Noclip => self.noclip,
+            MovementMode::Spectator => self.spectator,
+        }
+    }
+}
+
+impl Default for MovementSpeeds {
+    fn default() -> Self {
+        Self {
+            walk: 15.0,
+            fly: 30.0,
+            noclip: 30.0,
+            spectator: 40.0,
+        }
+    }
+}
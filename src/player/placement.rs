@@ -0,0 +1,297 @@
+use std::fmt;
+
+use cgmath::Vector3;
+
+use super::collision::Aabb;
+use crate::{
+    block::{self, Block, Model},
+    entity::EntityKind,
+    item,
+    terrain::{BlockPos, World},
+};
+
+/// Why a block was not placed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PlacementError {
+    /// The movement mode does not allow changing the world.
+    CannotInteract,
+    /// The selected hotbar slot is empty.
+    EmptyHand,
+    /// The selected item is a tool, not a block.
+    NotABlock,
+    /// No block within reach to place against.
+    NothingInReach,
+    /// The crosshair starts inside a block, leaving no face to place against.
+    NoFace,
+    /// A block that cannot be replaced is already there.
+    Occupied(BlockPos),
+    /// Ladders and vines hang on the side of a block, not on its top or bottom.
+    NeedsWall,
+    /// The block would end up inside the player.
+    OverlapsPlayer,
+    /// The block would end up inside a mob.
+    OverlapsEntity,
+}
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CannotInteract => write!(f, "blocks cannot be placed in this mode"),
+            Self::EmptyHand => write!(f, "nothing selected to place"),
+            Self::NotABlock => write!(f, "the selected item is not a block"),
+            Self::NothingInReach => write!(f, "nothing in reach to place against"),
+            Self::NoFace => write!(f, "no face to place against from inside a block"),
+            Self::Occupied(position) => write!(f, "{position:?} is already taken"),
+            Self::NeedsWall => write!(f, "it has to hang on the side of a block"),
+            Self::OverlapsPlayer => write!(f, "it would be inside the player"),
+            Self::OverlapsEntity => write!(f, "something is in the way"),
+        }
+    }
+}
+
+impl std::error::Error for PlacementError {}
+
+/// The block `block_id` turns into when placed at `target` against the face with `normal`,
+/// or why it cannot go there. Solid blocks may not overlap `player` or any of `entities`.
+pub fn check_placement(
+    world: &World,
+    target: BlockPos,
+    normal: Vector3<i32>,
+    block_id: u8,
+    player: &Aabb,
+    entities: &[Aabb],
+) -> Result<Block, PlacementError> {
+    if !item::is_block(block_id) {
+        return Err(PlacementError::NotABlock);
+    }
+    if normal == Vector3::new(0, 0, 0) {
+        return Err(PlacementError::NoFace);
+    }
+    if !world.is_replaceable(target) {
+        return Err(PlacementError::Occupied(target));
+    }
+    let properties = block::get_properties(block_id);
+    // Wall models hang on the face they were placed against, which has to be a side
+    let block_state = match properties.model {
+        Model::Cube => 0,
+        Model::Wall => block::wall_state(-normal).ok_or(PlacementError::NeedsWall)?,
+    };
+    if properties.solid {
+        let block = Aabb::block(target);
+        if block.intersects(player) {
+            return Err(PlacementError::OverlapsPlayer);
+        }
+        if entities.iter().any(|entity| block.intersects(entity)) {
+            return Err(PlacementError::OverlapsEntity);
+        }
+    }
+    Ok(Block {
+        block_id,
+        block_state,
+    })
+}
+
+/// Boxes of the entities blocks may not be placed in, which are only the mobs. Dropped items
+/// do not get in the way.
+pub fn blocking_entities(world: &World) -> Vec<Aabb> {
+    world
+        .entities
+        .iter()
+        .filter(|(_, entity)| matches!(entity.kind, EntityKind::Mob(_)))
+        .map(|(_, entity)| entity.aabb())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{entity::mob::Temper, player::inventory::ItemStack};
+
+    fn player_at(feet: [f32; 3]) -> Aabb {
+        Aabb::from_feet(feet.into(), 1.4, 2.8)
+    }
+
+    #[test]
+    fn solid_block_is_placed_next_to_the_player() {
+        let world = World::with_blocks(&[([3, 0, 0], 3)]);
+
+        let placed = check_placement(
+            &world,
+            [2, 0, 0].into(),
+            -Vector3::unit_x(),
+            3,
+            &player_at([0.5, 0.0, 0.5]),
+            &[],
+        );
+
+        assert_eq!(
+            placed,
+            Ok(Block {
+                block_id: 3,
+                block_state: 0
+            })
+        );
+    }
+
+    #[test]
+    fn solid_block_inside_the_player_is_refused() {
+        let world = World::with_blocks(&[([0, 0, 0], 3)]);
+
+        for target in [[0, 1, 0], [0, 3, 0], [1, 2, 0], [-1, 1, -1]] {
+            let placed = check_placement(
+                &world,
+                target.into(),
+                Vector3::unit_y(),
+                3,
+                &player_at([0.5, 1.0, 0.5]),
+                &[],
+            );
+            assert_eq!(placed, Err(PlacementError::OverlapsPlayer), "{target:?}");
+        }
+    }
+
+    #[test]
+    fn block_only_touching_the_player_is_placed() {
+        let world = World::with_blocks(&[]);
+
+        // the player stands exactly on the block and its side is flush with the next one
+        let player = player_at([0.7, 1.0, 0.5]);
+        for target in [[0, 0, 0], [2, 1, 0], [0, 4, 0]] {
+            let placed = check_placement(&world, target.into(), Vector3::unit_y(), 3, &player, &[]);
+            assert!(placed.is_ok(), "{target:?}");
+        }
+    }
+
+    #[test]
+    fn non_solid_blocks_may_overlap_the_player() {
+        let world = World::with_blocks(&[]);
+
+        let water = check_placement(
+            &world,
+            [0, 1, 0].into(),
+            Vector3::unit_y(),
+            0,
+            &player_at([0.5, 1.0, 0.5]),
+            &[],
+        );
+        let ladder = check_placement(
+            &world,
+            [0, 1, 0].into(),
+            -Vector3::unit_x(),
+            15,
+            &player_at([0.5, 1.0, 0.5]),
+            &[],
+        );
+
+        assert!(water.is_ok());
+        assert_eq!(ladder.map(|block| block.block_state), Ok(0));
+    }
+
+    #[test]
+    fn solid_block_inside_a_mob_is_refused() {
+        let mut world = World::with_blocks(&[([5, 0, 0], 3)]);
+        world.spawn_mob([5, 1, 0].into(), Temper::Tame);
+        let entities = blocking_entities(&world);
+
+        let stone = check_placement(
+            &world,
+            [5, 1, 0].into(),
+            Vector3::unit_y(),
+            3,
+            &player_at([0.5, 1.0, 0.5]),
+            &entities,
+        );
+        let water = check_placement(
+            &world,
+            [5, 1, 0].into(),
+            Vector3::unit_y(),
+            0,
+            &player_at([0.5, 1.0, 0.5]),
+            &entities,
+        );
+
+        assert_eq!(stone, Err(PlacementError::OverlapsEntity));
+        assert!(water.is_ok());
+    }
+
+    #[test]
+    fn dropped_items_do_not_block_placement() {
+        let mut world = World::with_blocks(&[([5, 0, 0], 3)]);
+        world.drop_item([5, 1, 0].into(), ItemStack { item: 2, count: 1 });
+
+        let stone = check_placement(
+            &world,
+            [5, 1, 0].into(),
+            Vector3::unit_y(),
+            3,
+            &player_at([0.5, 1.0, 0.5]),
+            &blocking_entities(&world),
+        );
+
+        assert!(stone.is_ok());
+    }
+
+    #[test]
+    fn occupied_target_is_refused() {
+        let world = World::with_blocks(&[([5, 5, 5], 3)]);
+
+        let placed = check_placement(
+            &world,
+            [5, 5, 5].into(),
+            Vector3::unit_y(),
+            3,
+            &player_at([0.5, 0.0, 0.5]),
+            &[],
+        );
+
+        assert_eq!(placed, Err(PlacementError::Occupied([5, 5, 5].into())));
+    }
+
+    #[test]
+    fn missing_face_is_refused() {
+        let world = World::with_blocks(&[]);
+
+        let placed = check_placement(
+            &world,
+            [5, 5, 5].into(),
+            Vector3::new(0, 0, 0),
+            3,
+            &player_at([0.5, 0.0, 0.5]),
+            &[],
+        );
+
+        assert_eq!(placed, Err(PlacementError::NoFace));
+    }
+
+    #[test]
+    fn tools_are_not_placed() {
+        let world = World::with_blocks(&[]);
+
+        let placed = check_placement(
+            &world,
+            [5, 5, 5].into(),
+            Vector3::unit_y(),
+            item::PICKAXE,
+            &player_at([0.5, 0.0, 0.5]),
+            &[],
+        );
+
+        assert_eq!(placed, Err(PlacementError::NotABlock));
+    }
+
+    #[test]
+    fn ladder_on_a_floor_is_refused() {
+        let world = World::with_blocks(&[]);
+
+        let placed = check_placement(
+            &world,
+            [5, 5, 5].into(),
+            Vector3::unit_y(),
+            15,
+            &player_at([0.5, 0.0, 0.5]),
+            &[],
+        );
+
+        assert_eq!(placed, Err(PlacementError::NeedsWall));
+    }
+}
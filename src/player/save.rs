@@ -0,0 +1,255 @@
+use std::{fs, io::Write, path::Path};
+
+use anyhow::{anyhow, bail, Result};
+use cgmath::{Point3, Rad};
+
+use super::{
+    game_mode::GameMode,
+    inventory::{Inventory, ItemStack, MAX_STACK_SIZE},
+    movement::MovementMode,
+    Player, MAX_AIR,
+};
+use crate::{
+    item,
+    terrain::save::{read_f32, read_u32, read_u8},
+};
+
+const VERSION: u32 = 4;
+
+impl Player {
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)?;
+
+        let mut data = Vec::new();
+        data.write_all(&VERSION.to_le_bytes())?;
+        for value in [
+            self.position.x,
+            self.position.y,
+            self.position.z,
+            self.camera.yaw.0,
+            self.camera.pitch.0,
+        ] {
+            data.write_all(&value.to_le_bytes())?;
+        }
+        data.write_all(&[self.mode.id()])?;
+        data.write_all(&self.health.current.to_le_bytes())?;
+        data.write_all(&self.air.to_le_bytes())?;
+        for value in [self.spawn_point.x, self.spawn_point.y, self.spawn_point.z] {
+            data.write_all(&value.to_le_bytes())?;
+        }
+        data.write_all(&[self.inventory.selected() as u8])?;
+        for slot in self.inventory.slots() {
+            let stack = slot.unwrap_or(ItemStack { item: 0, count: 0 });
+            data.write_all(&[stack.item, stack.count])?;
+        }
+        data.write_all(&[self.game_mode.id()])?;
+        fs::write(dir.join("player.dat"), data)?;
+        Ok(())
+    }
+
+    /// Restores the saved player. A missing save is not an error.
+    pub fn load(&mut self, dir: &Path) -> Result<()> {
+        let Ok(data) = fs::read(dir.join("player.dat")) else {
+            return Ok(());
+        };
+        self.read(&data)?;
+        // the saved movement mode has to be one the game mode allows, older saves could fly in
+        // survival
+        self.set_game_mode(self.game_mode);
+        Ok(())
+    }
+
+    fn read(&mut self, mut data: &[u8]) -> Result<()> {
+        // version 1 had no health, it loads with full health at the default spawn point.
+        // Versions before 3 had no inventory and keep the starting one, before 4 everyone played
+        // survival
+        let version = read_u32(&mut data)?;
+        if !(1..=VERSION).contains(&version) {
+            bail!("unsupported player save version");
+        }
+        self.position = Point3::new(
+            read_f32(&mut data)?,
+            read_f32(&mut data)?,
+            read_f32(&mut data)?,
+        );
+        self.previous_position = self.position;
+        self.camera.yaw = Rad(read_f32(&mut data)?);
+        self.camera.pitch = Rad(read_f32(&mut data)?);
+        let mode = read_u8(&mut data)?;
+        self.mode =
+            MovementMode::from_id(mode).ok_or_else(|| anyhow!("unknown movement mode {mode}"))?;
+        self.game_mode = GameMode::Survival;
+        if version == 1 {
+            return Ok(());
+        }
+        self.health.current = read_u32(&mut data)?.min(self.health.max);
+        self.air = read_u32(&mut data)?.min(MAX_AIR);
+        self.spawn_point = Point3::new(
+            read_f32(&mut data)?,
+            read_f32(&mut data)?,
+            read_f32(&mut data)?,
+        );
+        if version == 2 {
+            return Ok(());
+        }
+        let mut inventory = Inventory::empty();
+        inventory.select(read_u8(&mut data)? as usize);
+        for slot in 0..inventory.slots().len() {
+            let item = read_u8(&mut data)?;
+            let count = read_u8(&mut data)?;
+            if count > MAX_STACK_SIZE {
+                bail!("stack of {count} in slot {slot} is over the limit of {MAX_STACK_SIZE}");
+            }
+            // empty slots are written as item 0
+            if count > 0 && !item::is_known(item) {
+                bail!("unknown item {item} in slot {slot}");
+            }
+            inventory.set_slot(slot, Some(ItemStack { item, count }));
+        }
+        self.inventory = inventory;
+        if version == 3 {
+            return Ok(());
+        }
+        let game_mode = read_u8(&mut data)?;
+        self.game_mode =
+            GameMode::from_id(game_mode).ok_or_else(|| anyhow!("unknown game mode {game_mode}"))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn player_state_survives_a_save() {
+        let dir = std::env::temp_dir().join(format!("game-wgpu-player-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut player = Player::new([3.5, 20.25, -7.0].into());
+        player.camera.yaw = Rad(1.25);
+        player.camera.pitch = Rad(-0.5);
+        player.game_mode = GameMode::Creative;
+        player.mode = MovementMode::Noclip;
+        player.health.current = 7;
+        player.air = 12;
+        player.spawn_point = [1.0, 2.0, 3.0].into();
+        player.inventory = Inventory::empty();
+        player.inventory.add(7, 70);
+        player.inventory.select(4);
+
+        player.save(&dir).unwrap();
+        let mut loaded = Player::new([0.0, 0.0, 0.0].into());
+        loaded.load(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.position, player.position);
+        assert_eq!(loaded.camera.yaw, player.camera.yaw);
+        assert_eq!(loaded.camera.pitch, player.camera.pitch);
+        assert_eq!(loaded.mode, MovementMode::Noclip);
+        assert_eq!(loaded.game_mode, GameMode::Creative);
+        assert_eq!(loaded.health, player.health);
+        assert_eq!(loaded.air, 12);
+        assert_eq!(loaded.spawn_point, player.spawn_point);
+        assert_eq!(loaded.inventory, player.inventory);
+    }
+
+    #[test]
+    fn oversized_stacks_are_rejected() {
+        let dir = std::env::temp_dir().join(format!("game-wgpu-stacks-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut player = Player::new([0.0, 0.0, 0.0].into());
+        player.inventory = Inventory::empty();
+        player.inventory.add(7, 1);
+        player.save(&dir).unwrap();
+        let path = dir.join("player.dat");
+        let mut data = fs::read(&path).unwrap();
+        // the first slot's count, ahead of every other slot and the game mode
+        let count = data.len() - 1 - 2 * player.inventory.slots().len() + 1;
+        assert_eq!(data[count], 1);
+        data[count] = MAX_STACK_SIZE + 1;
+        fs::write(&path, data).unwrap();
+
+        let error = Player::new([0.0, 0.0, 0.0].into()).load(&dir).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(error.to_string().contains("over the limit"), "{error}");
+    }
+
+    #[test]
+    fn unknown_items_are_rejected() {
+        let dir = std::env::temp_dir().join(format!("game-wgpu-items-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut player = Player::new([0.0, 0.0, 0.0].into());
+        player.inventory = Inventory::empty();
+        player.inventory.add(7, 1);
+        player.save(&dir).unwrap();
+        let path = dir.join("player.dat");
+        let mut data = fs::read(&path).unwrap();
+        // the first slot's item, ahead of every other slot and the game mode
+        let item = data.len() - 1 - 2 * player.inventory.slots().len();
+        assert_eq!(data[item], 7);
+        data[item] = 200;
+        fs::write(&path, data).unwrap();
+
+        let error = Player::new([0.0, 0.0, 0.0].into()).load(&dir).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(error.to_string(), "unknown item 200 in slot 0");
+    }
+
+    #[test]
+    fn air_is_clamped_to_the_maximum() {
+        let dir = std::env::temp_dir().join(format!("game-wgpu-air-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut player = Player::new([0.0, 0.0, 0.0].into());
+        player.air = MAX_AIR * 10;
+        player.save(&dir).unwrap();
+
+        let mut loaded = Player::new([0.0, 0.0, 0.0].into());
+        loaded.air = 0;
+        loaded.load(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.air, MAX_AIR);
+    }
+
+    #[test]
+    fn flying_saves_without_a_game_mode_land_in_survival() {
+        let dir = std::env::temp_dir().join(format!("game-wgpu-v3-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut player = Player::new([0.0, 0.0, 0.0].into());
+        player.game_mode = GameMode::Creative;
+        player.mode = MovementMode::Fly;
+        player.save(&dir).unwrap();
+        let path = dir.join("player.dat");
+        // a version 3 save is the same without the game mode at the end
+        let mut data = fs::read(&path).unwrap();
+        data.pop();
+        data[..4].copy_from_slice(&3u32.to_le_bytes());
+        fs::write(&path, data).unwrap();
+
+        let mut loaded = Player::new([0.0, 0.0, 0.0].into());
+        loaded.game_mode = GameMode::Creative;
+        loaded.load(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.game_mode, GameMode::Survival);
+        assert_eq!(loaded.mode, MovementMode::Walk);
+    }
+
+    #[test]
+    fn survival_saves_cannot_noclip() {
+        let dir = std::env::temp_dir().join(format!("game-wgpu-noclip-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut player = Player::new([0.0, 0.0, 0.0].into());
+        player.game_mode = GameMode::Survival;
+        player.mode = MovementMode::Noclip;
+        player.save(&dir).unwrap();
+
+        let mut loaded = Player::new([0.0, 0.0, 0.0].into());
+        loaded.load(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.mode, MovementMode::Walk);
+    }
+}
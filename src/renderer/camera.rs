@@ -31,16 +31,23 @@ impl Projection {
         self.aspect = width as f32 / height as f32;
     }
 
-    pub fn calc_matrix(&self) -> Matrix4<f32> {
-        OPENGL_TO_WGPU_MATRIX * perspective(self.fovy, self.aspect, self.znear, self.zfar)
+    /// `fov_scale` widens the field of view, e.g. while sprinting.
+    pub fn calc_matrix(&self, fov_scale: f32) -> Matrix4<f32> {
+        OPENGL_TO_WGPU_MATRIX
+            * perspective(self.fovy * fov_scale, self.aspect, self.znear, self.zfar)
     }
 }
 
+/// Fog color in rgb and how thick it is in a.
+pub const AIR_FOG: [f32; 4] = [0.8, 0.8, 0.8, 1.0];
+pub const WATER_FOG: [f32; 4] = [0.1, 0.3, 0.6, 4.0];
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct CameraUniform {
     pub view_position: [f32; 4],
     pub view_proj: [[f32; 4]; 4],
+    pub fog: [f32; 4],
 }
 
 impl CameraUniform {
@@ -48,6 +55,7 @@ impl CameraUniform {
         Self {
             view_position: [0.0; 4],
             view_proj: cgmath::Matrix4::identity().into(),
+            fog: AIR_FOG,
         }
     }
 
@@ -64,6 +72,7 @@ impl CameraUniform {
 
     pub fn update_view_proj(&mut self, camera: &Camera, projection: &Projection) {
         self.view_position = camera.position.to_homogeneous().into();
-        self.view_proj = (projection.calc_matrix() * Self::camera_matrix(camera)).into();
+        self.view_proj =
+            (projection.calc_matrix(camera.fov_scale) * Self::camera_matrix(camera)).into();
     }
 }
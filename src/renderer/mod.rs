@@ -1,23 +1,36 @@
-use std::vec;
+use std::{collections::BTreeMap, mem, path::Path, vec};
 
+use cgmath::EuclideanSpace;
 use wgpu::{util::DeviceExt, Buffer, Surface};
 use winit::{
     dpi::PhysicalSize,
     event::*,
-    event_loop::{DeviceEvents, EventLoop, EventLoopWindowTarget},
+    event_loop::{EventLoop, EventLoopWindowTarget},
     keyboard::{KeyCode, PhysicalKey},
     window::{self, Window, WindowBuilder},
 };
 
 use crate::{
+    atlas::{Atlas, CRACK_STAGES},
+    crafting::Recipes,
+    entity::RenderModel,
+    input::{Action, Bindings, Input},
     player::*,
-    terrain::{self, instance::InstanceRaw, vertex::Vertex},
+    terrain::{
+        self,
+        instance::{Instance, InstanceRaw},
+        vertex::Vertex,
+    },
+    timestep::FixedTimestep,
 };
 
 mod camera;
 mod texture;
 
-const WORLD_SIZE: isize = 10;
+const WORLD_SIZE: i32 = 10;
+const SAVE_DIR: &str = "save";
+const TITLE: &str = "game-wgpu";
+const BINDINGS_FILE: &str = "controls.cfg";
 
 struct State<'w> {
     surface: wgpu::Surface<'w>,
@@ -32,11 +45,18 @@ struct State<'w> {
     //camera: Camera,
     player: Player,
     player_controller: controller::PlayerController,
+    bindings: Bindings,
+    recipes: Recipes,
     projection: camera::Projection,
     camera_uniform: camera::CameraUniform,
     camera_buffer: Buffer,
     camera_bind_group: wgpu::BindGroup,
     world: terrain::World,
+    /// Cracks over the block being broken, rebuilt when the block or the stage changes.
+    crack: Option<((terrain::BlockPos, u8), terrain::mesher::Mesh)>,
+    /// Entity models by item and size bits, kept between frames and only moved.
+    entities: BTreeMap<(u8, u32), terrain::mesher::Mesh>,
+    timestep: FixedTimestep,
     // Window last for safety
     window: Window,
 }
@@ -91,8 +111,7 @@ impl<'w> State<'w> {
             .formats
             .iter()
             .copied()
-            .filter(|f| f.is_srgb())
-            .next()
+            .find(|f| f.is_srgb())
             .unwrap_or(surface_caps.formats[0]);
 
         let config = wgpu::SurfaceConfiguration {
@@ -170,7 +189,7 @@ impl<'w> State<'w> {
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 entries: &[wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
@@ -247,9 +266,23 @@ impl<'w> State<'w> {
             multiview: None,
         });
 
-        let world = terrain::World::new(WORLD_SIZE, &device);
-        let player = Player::new([5.0, 16.0, 5.0].into());
-        let player_controller = controller::PlayerController::new(1.5, 0.2);
+        let mut world = terrain::World::new(WORLD_SIZE, &device);
+        if let Err(e) = world.load(Path::new(SAVE_DIR)) {
+            eprintln!("Could not load saved world: {e}");
+        }
+        if !world.has_mobs() {
+            world.spawn_mobs_around(world.spawn_point);
+        }
+        // player saves without a spawn point of their own keep the world's
+        let mut player = Player::new(world.spawn_point.bottom_center());
+        if let Err(e) = player.load(Path::new(SAVE_DIR)) {
+            eprintln!("Could not load saved player: {e}");
+        }
+        let player_controller = controller::PlayerController::new(15.0, 0.2);
+        let bindings = Bindings::load_or_create(Path::new(BINDINGS_FILE)).unwrap_or_else(|e| {
+            eprintln!("Could not load {BINDINGS_FILE}, using the default controls: {e}");
+            Bindings::default()
+        });
         Self {
             window,
             surface,
@@ -265,10 +298,15 @@ impl<'w> State<'w> {
             projection,
             player,
             player_controller,
+            bindings,
+            recipes: Recipes::default(),
             camera_uniform,
             camera_buffer,
             camera_bind_group,
             world,
+            crack: None,
+            entities: BTreeMap::new(),
+            timestep: FixedTimestep::new(),
         }
     }
 
@@ -295,28 +333,102 @@ impl<'w> State<'w> {
                     KeyEvent {
                         physical_key: PhysicalKey::Code(key),
                         state,
+                        repeat,
                         ..
                     },
                 ..
-            } => self.player_controller.process_keyboard(*key, *state),
+            } => self.input_action(Input::Key(*key), *state, *repeat),
             WindowEvent::MouseWheel { delta, .. } => {
                 self.player_controller.process_scroll(delta);
                 true
             }
             WindowEvent::MouseInput {
                 button: MouseButton::Left,
-                state,
                 ..
             } => true,
             _ => false,
         }
     }
 
+    /// Runs whatever `input` is bound to, returns false when it is not bound. Repeats of a held
+    /// key are dropped for actions that happen once per press.
+    fn input_action(&mut self, input: Input, state: ElementState, repeat: bool) -> bool {
+        if self.bindings.action(input).is_none() {
+            return false;
+        }
+        let Some(action) = self.bindings.key_action(input, repeat) else {
+            return true;
+        };
+        let pressed = state == ElementState::Pressed;
+        match action {
+            Action::Place if pressed => {
+                if let Err(e) = self
+                    .player_controller
+                    .place_block(&mut self.player, &mut self.world)
+                {
+                    eprintln!("Cannot place block: {e}");
+                }
+            }
+            Action::PickBlock if pressed => {
+                self.player_controller
+                    .pick_block(&mut self.player, &self.world);
+            }
+            Action::Craft if pressed => {
+                if self
+                    .player
+                    .inventory
+                    .craft_selected(&self.recipes)
+                    .is_none()
+                {
+                    eprintln!("Nothing to craft from the item in hand");
+                }
+            }
+            Action::Ignite if pressed => {
+                self.player_controller
+                    .ignite(&mut self.player, &mut self.world);
+            }
+            Action::HotbarSlot(slot) if pressed => self.player.inventory.select(slot as usize),
+            Action::Place
+            | Action::PickBlock
+            | Action::Craft
+            | Action::Ignite
+            | Action::HotbarSlot(_) => {}
+            action => {
+                self.player_controller.process_action(action, state);
+            }
+        }
+        true
+    }
+
     fn update(&mut self, dt: instant::Duration) {
-        self.player
-            .update(&mut self.player_controller, dt, &self.world);
+        for _ in 0..self.timestep.advance(dt) {
+            self.player.tick(&mut self.player_controller, &self.world);
+            self.player_controller
+                .update_breaking(&self.player, &mut self.world);
+            self.world.tick();
+            self.world.update_mobs(&self.player);
+            self.world.pick_up_items(&mut self.player);
+        }
+        self.update_crack();
+        self.update_entities();
+        if !self.player.take_events().is_empty() {
+            self.window.set_title(&format!(
+                "{TITLE} - health {}/{}",
+                self.player.health.current, self.player.health.max
+            ));
+        }
+        self.player.update_camera(
+            &mut self.player_controller,
+            dt.as_secs_f32(),
+            self.timestep.alpha(),
+        );
         self.camera_uniform
             .update_view_proj(&self.player.camera, &self.projection);
+        self.camera_uniform.fog = if self.player.eyes_in_fluid(&self.world) {
+            camera::WATER_FOG
+        } else {
+            camera::AIR_FOG
+        };
         self.queue.write_buffer(
             &self.camera_buffer,
             0,
@@ -324,8 +436,63 @@ impl<'w> State<'w> {
         );
     }
 
+    /// Moves the entity models to where the entities are this frame. Each model is built once
+    /// and its instances rewritten every frame, entities move and turn between ticks.
+    fn update_entities(&mut self) {
+        let alpha = self.timestep.alpha();
+        let mut instances: BTreeMap<(u8, u32), Vec<Instance>> = BTreeMap::new();
+        for (_, entity) in self.world.entities.iter() {
+            let Some(RenderModel::Cube { item, size }) = entity.model else {
+                continue;
+            };
+            let (center, rotation) = entity.pose(alpha);
+            instances
+                .entry((item, size.to_bits()))
+                .or_default()
+                .push(Instance {
+                    position: center.to_vec(),
+                    rotation,
+                });
+        }
+        for (key, mesh) in &mut self.entities {
+            if !instances.contains_key(key) {
+                mesh.set_instances(&[], &self.device, &self.queue);
+            }
+        }
+        for ((item, size), instances) in instances {
+            let mesh = self.entities.entry((item, size)).or_insert_with(|| {
+                terrain::mesher::get_item_mesh(item, f32::from_bits(size), &self.device)
+            });
+            mesh.set_instances(&instances, &self.device, &self.queue);
+        }
+    }
+
+    fn update_crack(&mut self) {
+        let crack = self.player_controller.breaking().map(|progress| {
+            let stage = (progress.fraction() * CRACK_STAGES as f32) as u8;
+            (progress.position, stage.min(CRACK_STAGES - 1))
+        });
+        if crack != self.crack.as_ref().map(|(crack, _)| *crack) {
+            self.crack = crack.map(|(position, stage)| {
+                let mesh =
+                    terrain::mesher::get_overlay_mesh(position, Atlas::Crack(stage), &self.device);
+                ((position, stage), mesh)
+            });
+        }
+    }
+
+    fn save(&self) {
+        if let Err(e) = self.world.save(Path::new(SAVE_DIR)) {
+            eprintln!("Could not save world: {e}");
+        }
+        if let Err(e) = self.player.save(Path::new(SAVE_DIR)) {
+            eprintln!("Could not save player: {e}");
+        }
+    }
+
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         let output = self.surface.get_current_texture()?;
+        let [r, g, b, _] = self.camera_uniform.fog.map(f64::from);
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
@@ -345,12 +512,7 @@ impl<'w> State<'w> {
                         view: &view,
                         resolve_target: None,
                         ops: wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(wgpu::Color {
-                                r: 0.8,
-                                g: 0.8,
-                                b: 0.8,
-                                a: 1.0,
-                            }),
+                            load: wgpu::LoadOp::Clear(wgpu::Color { r, g, b, a: 1.0 }),
                             store: wgpu::StoreOp::Store,
                         },
                     }),
@@ -378,6 +540,24 @@ impl<'w> State<'w> {
 
                 render_pass.draw_indexed(0..mesh.1.num_indices as u32, 0, 0..1);
             }
+            for mesh in self.entities.values().filter(|mesh| mesh.num_instances > 0) {
+                render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, mesh.instance_buffer.slice(..));
+                render_pass
+                    .set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(
+                    0..mesh.num_indices as u32,
+                    0,
+                    0..mesh.num_instances as u32,
+                );
+            }
+            if let Some((_, mesh)) = &self.crack {
+                render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, mesh.instance_buffer.slice(..));
+                render_pass
+                    .set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..mesh.num_indices as u32, 0, 0..1);
+            }
         }
         self.queue.submit(std::iter::once(encoder.finish()));
         output.present();
@@ -390,7 +570,10 @@ impl<'w> State<'w> {
 pub async fn run() {
     env_logger::init();
     let event_loop = EventLoop::new().unwrap();
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
+    let window = WindowBuilder::new()
+        .with_title(TITLE)
+        .build(&event_loop)
+        .unwrap();
     let mut state = State::new(window).await;
     state
         .window
@@ -407,26 +590,12 @@ pub async fn run() {
         Event::DeviceEvent {
             event:
                 DeviceEvent::Button {
-                    button: 0,
-                    state: ElementState::Pressed,
+                    button,
+                    state: button_state,
                 },
             ..
         } => {
-            state
-                .player_controller
-                .process_click(&state.player, &mut state.world, false);
-        }
-        Event::DeviceEvent {
-            event:
-                DeviceEvent::Button {
-                    button: 1,
-                    state: ElementState::Pressed,
-                },
-            ..
-        } => {
-            state
-                .player_controller
-                .process_click(&state.player, &mut state.world, true);
+            state.input_action(Input::Mouse(button), button_state, false);
         }
         Event::AboutToWait => {
             // RedrawRequested will only trigger once unless we manually
@@ -436,53 +605,52 @@ pub async fn run() {
         Event::WindowEvent {
             ref event,
             window_id,
-        } if window_id == state.window().id() => {
-            if !state.input(event) {
-                match event {
-                    WindowEvent::RedrawRequested if window_id == state.window().id() => {
-                        let now = instant::Instant::now();
-                        let dt = now - last_render_time;
-                        last_render_time = now;
-                        state.update(dt);
-                        for dirt in &state.world.dirty {
-                            state.world.meshes.remove(dirt);
-                            state.world.meshes.insert(
-                                *dirt,
-                                terrain::mesher::get_mesh(
-                                    &state.world.chunks,
-                                    *dirt,
-                                    &state.device,
-                                ),
-                            );
-                        }
-                        state.world.dirty = Vec::new();
-                        match state.render() {
-                            Ok(_) => {}
-                            // Reconfigure the surface if lost
-                            Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
-                            // The system is out of memory, we should probably quit
-                            Err(wgpu::SurfaceError::OutOfMemory) => {
-                                EventLoopWindowTarget::exit(control_flow)
-                            }
-                            // All other errors (Outdated, Timeout) should be resolved by the next frame
-                            Err(e) => eprintln!("{:?}", e),
+        } if window_id == state.window().id() && !state.input(event) => {
+            match event {
+                WindowEvent::RedrawRequested if window_id == state.window().id() => {
+                    let now = instant::Instant::now();
+                    let dt = now - last_render_time;
+                    last_render_time = now;
+                    state.update(dt);
+                    for dirt in mem::take(&mut state.world.dirty) {
+                        if !state.world.chunks.contains_key(&dirt) {
+                            continue;
                         }
+                        state.world.meshes.remove(&dirt);
+                        state.world.meshes.insert(
+                            dirt,
+                            terrain::mesher::get_mesh(&state.world.chunks, dirt, &state.device),
+                        );
                     }
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        event:
-                            KeyEvent {
-                                state: ElementState::Pressed,
-                                physical_key: PhysicalKey::Code(KeyCode::Escape),
-                                ..
-                            },
-                        ..
-                    } => control_flow.exit(),
-                    WindowEvent::Resized(physical_size) => {
-                        state.resize(*physical_size);
+                    match state.render() {
+                        Ok(_) => {}
+                        // Reconfigure the surface if lost
+                        Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
+                        // The system is out of memory, we should probably quit
+                        Err(wgpu::SurfaceError::OutOfMemory) => {
+                            EventLoopWindowTarget::exit(control_flow)
+                        }
+                        // All other errors (Outdated, Timeout) should be resolved by the next frame
+                        Err(e) => eprintln!("{:?}", e),
                     }
-                    _ => {}
                 }
+                WindowEvent::CloseRequested
+                | WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            state: ElementState::Pressed,
+                            physical_key: PhysicalKey::Code(KeyCode::Escape),
+                            ..
+                        },
+                    ..
+                } => {
+                    state.save();
+                    control_flow.exit()
+                }
+                WindowEvent::Resized(physical_size) => {
+                    state.resize(*physical_size);
+                }
+                _ => {}
             }
         }
         _ => {}
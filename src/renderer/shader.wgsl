@@ -11,6 +11,7 @@ struct InstanceInput {
 struct CameraUniform {
     view_position: vec4<f32>,
     view_proj: mat4x4<f32>,
+    fog: vec4<f32>,
 };
 @group(1) @binding(0) // 1.
 var<uniform> camera: CameraUniform;
@@ -82,9 +83,9 @@ fn fs_main(in: VertexOutput) -> FragmentOutput {
     var depth = in.clip_position.z / in.clip_position.w;
     var uvx = (in.clip_position.x/40)-10;
     var corrected_depth = sqrt(depth*depth + uvx*uvx);
-    var moved_depth = 2.0*max(corrected_depth/32.0 - 0.5, 0.0);
+    var moved_depth = 2.0*max(corrected_depth*camera.fog.a/32.0 - 0.5, 0.0);
     var fog = moved_depth*sqrt(moved_depth);
-    var fog_color =vec4(0.8,0.8,0.8,1.0);
+    var fog_color = vec4(camera.fog.rgb, 1.0);
 
     var out: FragmentOutput;
 
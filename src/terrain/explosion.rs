@@ -0,0 +1,247 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use cgmath::{InnerSpace, Point3, Vector3};
+
+use super::{BlockPos, World};
+
+/// Strength an explosion loses per block away from its center.
+const FALLOFF: f32 = 1.0;
+/// Knockback reaches this many times as far as blocks are removed.
+const KNOCKBACK_RANGE: f32 = 2.0;
+/// Blocks per second of knockback per unit of power, right at the center.
+const KNOCKBACK_SPEED: f32 = 4.0;
+
+/// A single blast.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Explosion {
+    pub center: Point3<f32>,
+    /// Strength at the center, which is also how many blocks away it runs out.
+    pub power: f32,
+}
+
+impl Explosion {
+    /// Strength left at `position`, blocks that resist less are removed.
+    fn strength_at(&self, position: Point3<f32>) -> f32 {
+        self.power - (position - self.center).magnitude() * FALLOFF
+    }
+
+    /// Velocity pushed onto something centered at `position`, away from the blast and weaker
+    /// further out.
+    pub fn knockback(&self, position: Point3<f32>) -> Vector3<f32> {
+        let range = self.power / FALLOFF * KNOCKBACK_RANGE;
+        let offset = position - self.center;
+        let distance = offset.magnitude();
+        if distance >= range {
+            return Vector3::new(0.0, 0.0, 0.0);
+        }
+        let direction = if distance > 1e-3 {
+            offset / distance
+        } else {
+            Vector3::unit_y()
+        };
+        direction * self.power * KNOCKBACK_SPEED * (1.0 - distance / range)
+    }
+}
+
+/// What an explosion did, along with every explosion it set off.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Blast {
+    /// In the order they went off, the first one first.
+    pub explosions: Vec<Explosion>,
+    /// Every block removed, sorted.
+    pub removed: Vec<BlockPos>,
+}
+
+impl Blast {
+    /// Knockback of all the explosions together.
+    pub fn knockback(&self, position: Point3<f32>) -> Vector3<f32> {
+        self.explosions
+            .iter()
+            .map(|explosion| explosion.knockback(position))
+            .sum()
+    }
+}
+
+impl World {
+    /// Sets off an explosion of `power` at `center`. See `blast` for what it does.
+    pub fn explode(&mut self, center: Point3<f32>, power: f32) -> Blast {
+        self.blast(Explosion { center, power }, BTreeSet::new())
+    }
+
+    /// Sets off the explosive block at `position` from its middle, `None` if it is not
+    /// explosive.
+    pub fn detonate(&mut self, position: BlockPos) -> Option<Blast> {
+        let power = self.get_block(position)?.properties().explosive?;
+        let explosion = Explosion {
+            center: position.center(),
+            power,
+        };
+        Some(self.blast(explosion, BTreeSet::from([position])))
+    }
+
+    /// Removes every block the explosion reaches with more strength than its blast
+    /// resistance, setting off the explosive ones among them in turn. Blocks are only removed
+    /// once every explosion has gone off, so each chunk is marked dirty once however many of
+    /// its blocks go. Entities are knocked back, the player is left to the caller.
+    fn blast(&mut self, first: Explosion, mut removed: BTreeSet<BlockPos>) -> Blast {
+        let mut explosions = Vec::new();
+        let mut queue = VecDeque::from([first]);
+        while let Some(explosion) = queue.pop_front() {
+            let origin = BlockPos::containing(explosion.center);
+            let reach = (explosion.power / FALLOFF).ceil() as i32;
+            for x in -reach..=reach {
+                for y in -reach..=reach {
+                    for z in -reach..=reach {
+                        let position = origin + Vector3::new(x, y, z);
+                        let Some(block) = self.get_block(position) else {
+                            continue;
+                        };
+                        let properties = block.properties();
+                        if removed.contains(&position)
+                            || explosion.strength_at(position.center())
+                                <= properties.blast_resistance
+                        {
+                            continue;
+                        }
+                        removed.insert(position);
+                        if let Some(power) = properties.explosive {
+                            queue.push_back(Explosion {
+                                center: position.center(),
+                                power,
+                            });
+                        }
+                    }
+                }
+            }
+            explosions.push(explosion);
+        }
+
+        for &position in &removed {
+            self.set_block(position, None);
+        }
+        let blast = Blast {
+            explosions,
+            removed: removed.into_iter().collect(),
+        };
+        for (_, entity) in self.entities.iter_mut() {
+            let height = entity.body.map_or(0.0, |body| body.height);
+            let center = entity.transform.position + Vector3::new(0.0, height / 2.0, 0.0);
+            if let Some(velocity) = &mut entity.velocity {
+                velocity.linear += blast.knockback(center);
+            }
+        }
+        blast
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::player::inventory::ItemStack;
+
+    const DIRT: u8 = 2;
+    const IRON_BARS: u8 = 7;
+    const TNT: u8 = 17;
+
+    fn ids(world: &World, positions: &[[i32; 3]]) -> Vec<Option<u8>> {
+        positions
+            .iter()
+            .map(|&position| world.get_block(position.into()).map(|block| block.block_id))
+            .collect()
+    }
+
+    #[test]
+    fn weak_blocks_near_the_center_are_removed() {
+        let mut world = World::with_blocks(&[
+            ([1, 0, 0], DIRT),
+            ([0, 1, 0], IRON_BARS),
+            ([0, 0, 0], 0),
+            ([5, 0, 0], DIRT),
+        ]);
+
+        let blast = world.explode([0.5, 0.5, 0.5].into(), 3.0);
+
+        assert_eq!(blast.removed, vec![[1, 0, 0].into()]);
+        assert_eq!(
+            ids(&world, &[[1, 0, 0], [0, 1, 0], [0, 0, 0], [5, 0, 0]]),
+            vec![None, Some(IRON_BARS), Some(0), Some(DIRT)]
+        );
+    }
+
+    #[test]
+    fn explosive_blocks_chain() {
+        let mut world =
+            World::with_blocks(&[([0, 0, 0], TNT), ([3, 0, 0], TNT), ([6, 0, 0], DIRT)]);
+
+        let blast = world.detonate([0, 0, 0].into()).unwrap();
+
+        assert_eq!(blast.explosions.len(), 2);
+        assert_eq!(
+            ids(&world, &[[0, 0, 0], [3, 0, 0], [6, 0, 0]]),
+            vec![None; 3]
+        );
+    }
+
+    #[test]
+    fn only_explosive_blocks_detonate() {
+        let mut world = World::with_blocks(&[([0, 0, 0], DIRT)]);
+
+        assert_eq!(world.detonate([0, 0, 0].into()), None);
+        assert_eq!(world.detonate([1, 0, 0].into()), None);
+        assert_eq!(ids(&world, &[[0, 0, 0]]), vec![Some(DIRT)]);
+    }
+
+    #[test]
+    fn each_changed_chunk_is_marked_dirty_once() {
+        let cube: Vec<([i32; 3], u8)> = (-2..=2)
+            .flat_map(|x| (-2..=2).flat_map(move |y| (-2..=2).map(move |z| ([x, y, z], DIRT))))
+            .collect();
+        let mut world = World::with_blocks(&cube);
+        world.dirty.clear();
+
+        let blast = world.explode([0.0, 0.0, 0.0].into(), 5.0);
+
+        assert_eq!(blast.removed.len(), cube.len());
+        let corners: HashSet<_> = (-1..=0)
+            .flat_map(|x| (-1..=0).flat_map(move |y| (-1..=0).map(move |z| [x, y, z].into())))
+            .collect();
+        assert_eq!(world.dirty, corners);
+    }
+
+    #[test]
+    fn knockback_pushes_away_and_fades() {
+        let explosion = Explosion {
+            center: [0.0, 0.0, 0.0].into(),
+            power: 2.0,
+        };
+
+        let near = explosion.knockback([1.0, 0.0, 0.0].into());
+        let far = explosion.knockback([3.0, 0.0, 0.0].into());
+
+        assert!(near.x > far.x && far.x > 0.0, "{near:?} {far:?}");
+        assert_eq!(near.y, 0.0);
+        assert_eq!(
+            explosion.knockback([0.0, 0.0, -5.0].into()),
+            Vector3::new(0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn entities_are_knocked_back() {
+        let mut world = World::with_blocks(&[]);
+        let id = world.drop_item(
+            [2, 0, 0].into(),
+            ItemStack {
+                item: DIRT,
+                count: 1,
+            },
+        );
+
+        world.explode([0.5, 0.5, 0.5].into(), 3.0);
+
+        let velocity = world.entities.get(id).unwrap().velocity.unwrap();
+        assert!(velocity.linear.x > 0.0, "{velocity:?}");
+    }
+}
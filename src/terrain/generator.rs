@@ -0,0 +1,119 @@
+use noise::{utils::*, Fbm, Perlin};
+
+use super::{Chunk, ChunkPos, LocalPos, CHUNK_SIZE};
+use crate::block::Block;
+
+/// Fills in chunks the first time they are loaded.
+pub trait Generator {
+    fn generate(&self, chunk_pos: ChunkPos) -> Chunk;
+}
+
+/// Rolling hills of layered Perlin noise with water below y = 0.
+pub struct NoiseGenerator {
+    perlin: Fbm<Perlin>,
+}
+
+impl NoiseGenerator {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            perlin: Fbm::<Perlin>::new(seed),
+        }
+    }
+}
+
+impl Generator for NoiseGenerator {
+    fn generate(&self, chunk_pos: ChunkPos) -> Chunk {
+        gen_chunk(chunk_pos.x, chunk_pos.y, chunk_pos.z, &self.perlin)
+    }
+}
+
+/// The same stack of layers everywhere, listed from y = 0 upwards. Nothing below 0.
+pub struct FlatGenerator {
+    pub layers: Vec<u8>,
+}
+
+impl Generator for FlatGenerator {
+    fn generate(&self, chunk_pos: ChunkPos) -> Chunk {
+        let mut chunk = Chunk::new();
+        for (y, block_id) in self.layers.iter().enumerate() {
+            let position = chunk_pos.block(LocalPos::new(0, 0, 0));
+            let local_y = y as i32 - position.y;
+            if !(0..CHUNK_SIZE as i32).contains(&local_y) {
+                continue;
+            }
+            for x in 0..CHUNK_SIZE as u8 {
+                for z in 0..CHUNK_SIZE as u8 {
+                    chunk.insert(
+                        LocalPos::new(x, local_y as u8, z),
+                        Block {
+                            block_id: *block_id,
+                            block_state: 0,
+                        },
+                    );
+                }
+            }
+        }
+        chunk
+    }
+}
+
+fn gen_chunk(chunk_x: i32, chunk_y: i32, chunk_z: i32, perlin: &Fbm<Perlin>) -> Chunk {
+    let mut voxels = Chunk::new();
+
+    let k = 0.125;
+    let k2 = k * 2.0;
+    let map = PlaneMapBuilder::<_, 2>::new(perlin)
+        .set_size(CHUNK_SIZE, CHUNK_SIZE)
+        .set_x_bounds(
+            -k2 + 2.0 * k2 * chunk_x as f64,
+            k2 + 2.0 * k2 * chunk_x as f64,
+        )
+        .set_y_bounds(
+            -k2 + 2.0 * k2 * chunk_z as f64,
+            k2 + 2.0 * k2 * chunk_z as f64,
+        )
+        .build();
+    let big_map = PlaneMapBuilder::<_, 2>::new(perlin)
+        .set_size(CHUNK_SIZE, CHUNK_SIZE)
+        .set_x_bounds(-k + 2.0 * k * chunk_x as f64, k + 2.0 * k * chunk_x as f64)
+        .set_y_bounds(-k + 2.0 * k * chunk_z as f64, k + 2.0 * k * chunk_z as f64)
+        .build();
+
+    for x in 0..CHUNK_SIZE {
+        for y in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                //let sample_x = x as isize + chunk_x * CHUNK_SIZE as isize;
+                //let sample_z = z as isize + chunk_z * CHUNK_SIZE as isize;
+                let global_y = y as i32 + chunk_y * CHUNK_SIZE as i32;
+                let generated_id;
+
+                let val1 = map.get_value(x, z);
+                let val2 = big_map.get_value(x, z);
+                let depth = val1 * 4.0 + val2 * 8.0 - global_y as f64 + 0.0;
+
+                if depth < 0.0 && global_y < 0 {
+                    generated_id = 0;
+                } else if depth < 0.0 {
+                    continue;
+                } else if depth < 1.0 && global_y < 2 {
+                    generated_id = 4;
+                } else if depth < 1.0 {
+                    generated_id = 1;
+                } else if depth < 2.0 {
+                    generated_id = 2;
+                } else {
+                    generated_id = 3;
+                }
+
+                voxels.insert(
+                    LocalPos::new(x as u8, y as u8, z as u8),
+                    Block {
+                        block_id: generated_id as u8,
+                        block_state: 0,
+                    },
+                );
+            }
+        }
+    }
+    voxels
+}
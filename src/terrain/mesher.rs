@@ -2,22 +2,32 @@ use super::vertex::*;
 use super::*;
 use crate::atlas::*;
 use crate::block;
+use crate::block::Block;
 use cgmath::Array;
 use cgmath::Deg;
+use cgmath::EuclideanSpace;
+use cgmath::Point3;
 use cgmath::Quaternion;
 use cgmath::Rotation3;
 use cgmath::Vector3;
+use std::mem;
 use wgpu::util::DeviceExt;
 use wgpu::Buffer;
 use wgpu::Device;
+use wgpu::Queue;
+
+/// Gap between a wall model and the wall it hangs on.
+const WALL_OFFSET: f32 = 1.0 / 16.0;
+/// How far overlays stand out from the faces of their block so they are drawn over them.
+const OVERLAY_INFLATE: f32 = 0.005;
 
 fn offset_indices(offset: u32, flip: bool) -> [u32; 6] {
     //let [a, b, c, d, e, f] = face;
     let mut ret = [
-        0 + offset,
+        offset,
         2 + offset,
         3 + offset,
-        0 + offset,
+        offset,
         3 + offset,
         1 + offset,
     ];
@@ -93,7 +103,7 @@ fn get_face(
     [
         Vertex {
             position: corners[0],
-            tex_coords: textures[0 + flip],
+            tex_coords: textures[flip],
             normal: normal_dir,
             ao: occluders[0] + occluders[1] + occluders[2],
         },
@@ -118,6 +128,23 @@ fn get_face(
     ]
 }
 
+/// Both sides of the panel of a `Model::Wall` block, with the normal facing it.
+fn get_wall_faces(block: &Block, position: Point3<i8>) -> [(Vector3<i8>, [Vertex; 4]); 2] {
+    let wall = block::wall_direction(block.block_state)
+        .cast::<i8>()
+        .unwrap();
+    [(-wall, 1.0 - WALL_OFFSET), (wall, -WALL_OFFSET)].map(|(normal, shift)| {
+        let texture = block::get_texture(block.block_id, normal.into());
+        let mut face = get_face(normal, texture, position, [0.33; 8]);
+        for vertex in &mut face {
+            for axis in 0..3 {
+                vertex.position[axis] += wall[axis] as f32 * shift;
+            }
+        }
+        (normal, face)
+    })
+}
+
 fn get_occluders(position: Point3<i8>, normal: Vector3<i8>) -> [Point3<i8>; 8] {
     let h = Vector3 {
         x: normal.y,
@@ -161,22 +188,17 @@ fn outside_chunk(position: &Point3<i8>) -> bool {
 }
 
 fn check_neighbor_at_edge_of_chunk(
-    chunks: &HashMap<Point3<isize>, Chunk>,
-    chunk_pos: &Point3<isize>,
+    chunks: &HashMap<ChunkPos, Chunk>,
+    chunk_pos: &ChunkPos,
     normal: &Vector3<i8>,
     neighbor: &Point3<i8>,
     self_id: u8,
 ) -> bool {
-    let neighbor_chunk = &[
-        chunk_pos.x + normal.x as isize,
-        chunk_pos.y + normal.y as isize,
-        chunk_pos.z + normal.z as isize,
-    ]
-    .into();
+    let neighbor_chunk = &(*chunk_pos + normal.cast().unwrap());
     let relative_pos = Point3 {
-        x: (neighbor.x + CHUNK_SIZE as i8 * -normal.x) as i8,
-        y: (neighbor.y + CHUNK_SIZE as i8 * -normal.y) as i8,
-        z: (neighbor.z + CHUNK_SIZE as i8 * -normal.z) as i8,
+        x: neighbor.x + CHUNK_SIZE as i8 * -normal.x,
+        y: neighbor.y + CHUNK_SIZE as i8 * -normal.y,
+        z: neighbor.z + CHUNK_SIZE as i8 * -normal.z,
     };
     block_opaque(chunks, neighbor_chunk, &relative_pos, self_id)
 }
@@ -190,33 +212,26 @@ fn get_relative_chunk(position: &Point3<i8>) -> Vector3<i8> {
     let larger_than_chunk_z = (position.z >= CHUNK_SIZE as i8) as i8;
 
     Vector3 {
-        x: less_than_zero_x * -1 + larger_than_chunk_x,
-        y: less_than_zero_y * -1 + larger_than_chunk_y,
-        z: less_than_zero_z * -1 + larger_than_chunk_z,
+        x: -less_than_zero_x + larger_than_chunk_x,
+        y: -less_than_zero_y + larger_than_chunk_y,
+        z: -less_than_zero_z + larger_than_chunk_z,
     }
 }
 pub fn block_opaque(
-    chunks: &HashMap<Point3<isize>, Chunk>,
-    chunk_pos: &Point3<isize>,
+    chunks: &HashMap<ChunkPos, Chunk>,
+    chunk_pos: &ChunkPos,
     relative_pos: &Point3<i8>,
     self_id: u8,
 ) -> bool {
-    let chunk = chunks.get(chunk_pos);
-
-    if chunk.is_none() {
+    let Some(chunk) = chunks.get(chunk_pos) else {
         return false;
-    }
+    };
 
-    let block = chunk.unwrap().get(relative_pos);
-    if block.is_some() {
-        if block.unwrap().block_id == 7
-            || block.unwrap().block_id == 9
-            || block.unwrap().block_id == 0
-        {
-            return block.unwrap().block_id == self_id;
-        }
-    } else {
+    let Some(block) = LocalPos::from_point(*relative_pos).and_then(|pos| chunk.get(&pos)) else {
         return false;
+    };
+    if block::is_transparent(block.block_id) {
+        return block.block_id == self_id;
     }
 
     true
@@ -227,28 +242,53 @@ pub struct Mesh {
     pub index_buffer: Buffer,
     pub instance_buffer: Buffer,
     pub num_indices: usize,
+    pub num_instances: usize,
 }
 
-pub fn get_mesh(
-    chunks: &HashMap<Point3<isize>, Chunk>,
-    chunk_pos: Point3<isize>,
-    device: &Device,
-) -> Mesh {
+impl Mesh {
+    /// Replaces where the mesh is drawn. The instance buffer is reused and only replaced by a
+    /// larger one when there are more instances than fit, so this is cheap to call every frame
+    /// on meshes from `get_item_mesh`.
+    pub fn set_instances(
+        &mut self,
+        instances: &[instance::Instance],
+        device: &Device,
+        queue: &Queue,
+    ) {
+        let capacity =
+            self.instance_buffer.size() as usize / mem::size_of::<instance::InstanceRaw>();
+        if instances.len() > capacity {
+            self.instance_buffer = instance_buffer(instances.len().next_power_of_two(), device);
+        }
+        if !instances.is_empty() {
+            let data: Vec<_> = instances.iter().map(instance::Instance::to_raw).collect();
+            queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&data));
+        }
+        self.num_instances = instances.len();
+    }
+}
+
+pub fn get_mesh(chunks: &HashMap<ChunkPos, Chunk>, chunk_pos: ChunkPos, device: &Device) -> Mesh {
     let mut vertices: Vec<Vertex> = Vec::new();
     let mut indices: Vec<u32> = Vec::new();
     let mut off: u32 = 0;
 
     let voxeldata = chunks.get(&chunk_pos).unwrap();
     for block in voxeldata {
+        if block.1.properties().model == block::Model::Wall {
+            for (normal, face) in get_wall_faces(block.1, block.0.to_point()) {
+                vertices.extend(face);
+                indices.extend(offset_indices(off, normal.sum().is_negative()));
+                off += 4;
+            }
+            continue;
+        }
         for face in 0..6 {
             let normal = get_normal(face);
-            let block_pos: &Point3<i8> =
-                &[block.0.x as i8, block.0.y as i8, block.0.z as i8].into();
+            let block_pos = block.0.to_point();
             let neighbor_position = block_pos + normal;
-            let mut neighbor_chunk = chunk_pos;
-
             if outside_chunk(&neighbor_position) {
-                neighbor_chunk += normal.cast().unwrap();
+                let neighbor_chunk = chunk_pos + normal.cast().unwrap();
                 if block_opaque(
                     chunks,
                     &neighbor_chunk,
@@ -265,8 +305,7 @@ pub fn get_mesh(
 
             let occluders_pos = get_occluders(neighbor_position, normal);
 
-            let mut i = 0;
-            for pos in occluders_pos {
+            for (i, pos) in occluders_pos.into_iter().enumerate() {
                 if outside_chunk(&pos) {
                     occluders[i] = !check_neighbor_at_edge_of_chunk(
                         chunks,
@@ -281,47 +320,123 @@ pub fn get_mesh(
                         as f32
                         * 0.33;
                 }
-                i += 1;
             }
 
             let flip = normal.sum().is_negative();
             let texture = block::get_texture(block.1.block_id, normal.into());
-            vertices.extend(get_face(normal, texture, *block.0, occluders));
+            vertices.extend(get_face(normal, texture, block_pos, occluders));
             indices.extend(offset_indices(off, flip));
             off += 4;
         }
     }
 
+    upload(&vertices, &indices, &[at(chunk_pos.min_corner())], device)
+}
+
+/// A cube just around the block at `position` with `texture` on every side, for drawing
+/// something like cracks over the block.
+pub fn get_overlay_mesh(position: BlockPos, texture: Atlas, device: &Device) -> Mesh {
+    let mut vertices: Vec<Vertex> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    for face in 0..6 {
+        let normal = get_normal(face);
+        let mut corners = get_face(normal, texture, Point3::new(0, 0, 0), [0.33; 8]);
+        for vertex in &mut corners {
+            for axis in 0..3 {
+                vertex.position[axis] =
+                    0.5 + (vertex.position[axis] - 0.5) * (1.0 + 2.0 * OVERLAY_INFLATE);
+            }
+        }
+        indices.extend(offset_indices(
+            vertices.len() as u32,
+            normal.sum().is_negative(),
+        ));
+        vertices.extend(corners);
+    }
+    upload(&vertices, &indices, &[at(position.min_corner())], device)
+}
+
+/// A cube `size` wide textured like the block `item`, centered on its origin. It starts out
+/// drawn nowhere, place it with `Mesh::set_instances`. Used for entity models.
+pub fn get_item_mesh(item: u8, size: f32, device: &Device) -> Mesh {
+    let mut vertices: Vec<Vertex> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    for face in 0..6 {
+        let normal = get_normal(face);
+        let texture = block::get_texture(item, normal.into());
+        let mut corners = get_face(normal, texture, Point3::new(0, 0, 0), [0.33; 8]);
+        for vertex in &mut corners {
+            for axis in 0..3 {
+                vertex.position[axis] = (vertex.position[axis] - 0.5) * size;
+            }
+        }
+        indices.extend(offset_indices(
+            vertices.len() as u32,
+            normal.sum().is_negative(),
+        ));
+        vertices.extend(corners);
+    }
+    build(&vertices, &indices, instance_buffer(1, device), 0, device)
+}
+
+/// An unrotated instance with its local (0, 0, 0) at `origin`.
+fn at(origin: Point3<f32>) -> instance::Instance {
+    instance::Instance {
+        position: origin.to_vec(),
+        rotation: Quaternion::from_axis_angle(Vector3::unit_z(), Deg(0.0)),
+    }
+}
+
+/// Puts a mesh on the GPU, drawn once for each of `instances`.
+fn upload(
+    vertices: &[Vertex],
+    indices: &[u32],
+    instances: &[instance::Instance],
+    device: &Device,
+) -> Mesh {
+    let instance_data: Vec<_> = instances.iter().map(instance::Instance::to_raw).collect();
+    let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Instance Buffer"),
+        contents: bytemuck::cast_slice(&instance_data),
+        usage: wgpu::BufferUsages::VERTEX,
+    });
+    build(vertices, indices, instance_buffer, instances.len(), device)
+}
+
+/// An instance buffer with room for `capacity` instances that can be written to later.
+fn instance_buffer(capacity: usize, device: &Device) -> Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Instance Buffer"),
+        size: (capacity * mem::size_of::<instance::InstanceRaw>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+/// Puts the vertices and indices on the GPU next to an existing instance buffer.
+fn build(
+    vertices: &[Vertex],
+    indices: &[u32],
+    instance_buffer: Buffer,
+    num_instances: usize,
+    device: &Device,
+) -> Mesh {
     let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Vertex Buffer"),
-        contents: bytemuck::cast_slice(&vertices),
+        contents: bytemuck::cast_slice(vertices),
         usage: wgpu::BufferUsages::VERTEX,
     });
 
     let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Index Buffer"),
-        contents: bytemuck::cast_slice(&indices),
+        contents: bytemuck::cast_slice(indices),
         usage: wgpu::BufferUsages::INDEX,
     });
-
-    let instance = instance::Instance {
-        position: Vector3 {
-            x: chunk_pos[0] as f32 * CHUNK_SIZE as f32,
-            y: chunk_pos[1] as f32 * CHUNK_SIZE as f32,
-            z: chunk_pos[2] as f32 * CHUNK_SIZE as f32,
-        },
-        rotation: Quaternion::from_axis_angle(Vector3::unit_z(), Deg(0.0)),
-    };
-    let instance_data = vec![instance.to_raw()];
-    let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Instance Buffer"),
-        contents: bytemuck::cast_slice(&instance_data),
-        usage: wgpu::BufferUsages::VERTEX,
-    });
     Mesh {
         vertex_buffer,
         index_buffer,
         instance_buffer,
         num_indices: indices.len(),
+        num_instances,
     }
 }
@@ -1,286 +1,286 @@
-use self::mesher::{get_mesh, Mesh};
-
 use super::block::Block;
-use cgmath::{num_traits::abs, Point3, Vector3};
-use image::buffer;
-use noise::{utils::*, Fbm, Perlin};
-use std::collections::HashMap;
+use crate::entity::Entities;
+use cgmath::Vector3;
+use std::collections::{HashMap, HashSet, VecDeque};
 use wgpu::Device;
+pub mod explosion;
+pub mod generator;
 pub mod instance;
 pub(crate) mod mesher;
+pub mod position;
+pub mod raycast;
+pub(crate) mod save;
+pub mod spawn;
+pub mod tick;
+pub mod update;
 pub mod vertex;
 
 pub const CHUNK_SIZE: usize = 16;
+const SEED: u32 = 696969;
 
-pub type Chunk = HashMap<Point3<i8>, Block>;
-
-fn gen_chunk(
-    chunk_x: isize,
-    chunk_y: isize,
-    chunk_z: isize,
-    perlin: &Fbm<Perlin>,
-) -> HashMap<Point3<i8>, Block> {
-    let mut voxels: HashMap<Point3<i8>, Block> = HashMap::new();
-
-    let k = 0.125;
-    let k2 = k * 2.0;
-    let map = PlaneMapBuilder::<_, 2>::new(perlin)
-        .set_size(CHUNK_SIZE, CHUNK_SIZE)
-        .set_x_bounds(
-            -k2 + 2.0 * k2 * chunk_x as f64,
-            k2 + 2.0 * k2 * chunk_x as f64,
-        )
-        .set_y_bounds(
-            -k2 + 2.0 * k2 * chunk_z as f64,
-            k2 + 2.0 * k2 * chunk_z as f64,
-        )
-        .build();
-    let big_map = PlaneMapBuilder::<_, 2>::new(perlin)
-        .set_size(CHUNK_SIZE, CHUNK_SIZE)
-        .set_x_bounds(-k + 2.0 * k * chunk_x as f64, k + 2.0 * k * chunk_x as f64)
-        .set_y_bounds(-k + 2.0 * k * chunk_z as f64, k + 2.0 * k * chunk_z as f64)
-        .build();
-
-    for x in 0..CHUNK_SIZE {
-        for y in 0..CHUNK_SIZE {
-            for z in 0..CHUNK_SIZE {
-                //let sample_x = x as isize + chunk_x * CHUNK_SIZE as isize;
-                //let sample_z = z as isize + chunk_z * CHUNK_SIZE as isize;
-                let global_y = y as isize + chunk_y * CHUNK_SIZE as isize;
-                let generated_id;
+pub use generator::{FlatGenerator, Generator, NoiseGenerator};
+pub use position::{BlockPos, ChunkPos, LocalPos};
 
-                let val1 = map.get_value(x, z);
-                let val2 = big_map.get_value(x, z);
-                let depth = val1 * 4.0 + val2 * 8.0 - global_y as f64 + 0.0;
-
-                if depth < 0.0 && global_y < 0 {
-                    generated_id = 0;
-                } else if depth < 0.0 {
-                    continue;
-                } else if depth < 1.0 && global_y < 2 {
-                    generated_id = 4;
-                } else if depth < 1.0 {
-                    generated_id = 1;
-                } else if depth < 2.0 {
-                    generated_id = 2;
-                } else {
-                    generated_id = 3;
-                }
-
-                voxels.insert(
-                    [x as i8, y as i8, z as i8].into(),
-                    Block {
-                        block_id: generated_id as u8,
-                        block_state: 0,
-                    },
-                );
-            }
-        }
-    }
-    voxels
-}
+pub type Chunk = HashMap<LocalPos, Block>;
 
 pub struct World {
-    pub chunks: HashMap<Point3<isize>, Chunk>,
-    pub meshes: HashMap<Point3<isize>, mesher::Mesh>,
-    pub dirty: Vec<Point3<isize>>,
+    pub chunks: HashMap<ChunkPos, Chunk>,
+    pub meshes: HashMap<ChunkPos, mesher::Mesh>,
+    /// Chunks to remesh, each listed once however many of its blocks changed.
+    pub dirty: HashSet<ChunkPos>,
+    /// How many blocks are picked per loaded chunk every random tick.
+    pub random_tick_speed: u32,
+    rng: tick::TickRng,
+    /// Ticks since the world was created, scheduled ticks are due relative to this.
+    pub game_tick: u64,
+    scheduled: Vec<update::ScheduledTick>,
+    pending_updates: VecDeque<BlockPos>,
+    /// Chunks that differ from what the generator made and have to be saved.
+    edited: HashSet<ChunkPos>,
+    /// Block the player spawns and respawns in, standing on whatever is below it.
+    pub spawn_point: BlockPos,
+    /// Everything that moves apart from the player.
+    pub entities: Entities,
 }
 
 impl World {
-    pub fn new(world_size: isize, device: &Device) -> Self {
-        let perlin = Fbm::<Perlin>::new(696969);
-
-        let mut chunks = HashMap::new();
-        chunks.insert([0, 0, 0].into(), HashMap::new());
+    pub fn new(world_size: i32, device: &Device) -> Self {
+        let mut world = Self::generate(&NoiseGenerator::new(SEED), world_size, SEED as u64);
 
         let todo = world_size * (world_size + 1) * 6 - world_size * 2;
         for x in -world_size..=world_size {
             for y in -1..=1 {
                 for z in -world_size..=world_size {
-                    chunks.insert([x, y, z].into(), gen_chunk(x, y, z, &perlin));
+                    world.meshes.insert(
+                        [x, y, z].into(),
+                        mesher::get_mesh(&world.chunks, [x, y, z].into(), device),
+                    );
                     let progress =
                         (z + world_size) + (y + 1) * world_size + (x + world_size) * world_size * 3;
-                    println!("Generating terrain: {progress}/{todo}")
+                    println!("Generating mesh: {progress}/{todo}")
                 }
             }
         }
 
-        let mut meshes = HashMap::new();
+        world
+    }
+    /// Generates the chunks within `world_size` of the origin and picks a spawn point, without
+    /// meshing anything.
+    pub fn generate(generator: &impl Generator, world_size: i32, seed: u64) -> Self {
+        let mut chunks = HashMap::new();
+        chunks.insert([0, 0, 0].into(), HashMap::new());
 
+        let todo = world_size * (world_size + 1) * 6 - world_size * 2;
         for x in -world_size..=world_size {
             for y in -1..=1 {
                 for z in -world_size..=world_size {
-                    meshes.insert(
-                        [x, y, z].into(),
-                        mesher::get_mesh(&chunks, [x, y, z].into(), device),
-                    );
+                    chunks.insert([x, y, z].into(), generator.generate([x, y, z].into()));
                     let progress =
                         (z + world_size) + (y + 1) * world_size + (x + world_size) * world_size * 3;
-                    println!("Generating mesh: {progress}/{todo}")
+                    println!("Generating terrain: {progress}/{todo}")
                 }
             }
         }
 
+        let mut world = Self::from_chunks(chunks, seed);
+        world.spawn_point = world
+            .find_spawn(spawn::SPAWN_HEADROOM)
+            .unwrap_or(spawn::FALLBACK_SPAWN);
+        world
+    }
+    /// Builds a world from already generated chunks without meshing them.
+    pub fn from_chunks(chunks: HashMap<ChunkPos, Chunk>, seed: u64) -> Self {
         World {
             chunks,
-            meshes,
-            dirty: Vec::new(),
+            meshes: HashMap::new(),
+            dirty: HashSet::new(),
+            random_tick_speed: 3,
+            rng: tick::TickRng::new(seed),
+            game_tick: 0,
+            scheduled: Vec::new(),
+            pending_updates: VecDeque::new(),
+            edited: HashSet::new(),
+            spawn_point: spawn::FALLBACK_SPAWN,
+            entities: Entities::default(),
         }
     }
-    pub fn get_chunk(&self, x: isize, y: isize, z: isize) -> &Chunk {
-        if self.chunks.contains_key(&[x, y, z].into()) {
-            self.chunks.get(&[x, y, z].into()).unwrap()
-        } else {
-            self.chunks.get(&[0, 0, 0].into()).unwrap()
-        }
-    }
-    pub fn block_exists(&self, position: Point3<f32>) -> bool {
-        let (chunk_pos, relative_pos) = self.chunk_block_from_global(position);
-        if !self.chunks.contains_key(&chunk_pos) {
-            return false;
+    /// Empty chunks around the origin with `blocks` placed in them, for tests.
+    #[cfg(test)]
+    pub fn with_blocks(blocks: &[([i32; 3], u8)]) -> Self {
+        let mut chunks = HashMap::new();
+        for x in -1..=1 {
+            for y in -1..=1 {
+                for z in -1..=1 {
+                    chunks.insert([x, y, z].into(), Chunk::new());
+                }
+            }
         }
-
-        let chunk = self.chunks.get(&chunk_pos).unwrap();
-        if !chunk.contains_key(&relative_pos) {
-            return false;
+        let mut world = World::from_chunks(chunks, 0);
+        for (position, block_id) in blocks {
+            world.set_block(
+                (*position).into(),
+                Some(Block {
+                    block_id: *block_id,
+                    block_state: 0,
+                }),
+            );
         }
-
-        true
+        world
+    }
+    pub fn get_chunk(&self, position: ChunkPos) -> Option<&Chunk> {
+        self.chunks.get(&position)
+    }
+    pub fn block_exists(&self, position: BlockPos) -> bool {
+        self.get_block(position).is_some()
+    }
+    pub fn is_solid(&self, position: BlockPos) -> bool {
+        self.get_block(position)
+            .is_some_and(|block| block.properties().solid)
+    }
+    pub fn is_fluid(&self, position: BlockPos) -> bool {
+        self.get_block(position)
+            .is_some_and(|block| block.properties().fluid)
+    }
+    pub fn is_climbable(&self, position: BlockPos) -> bool {
+        self.get_block(position)
+            .is_some_and(|block| block.properties().climbable)
+    }
+    /// Empty space or a block like water that placing simply overwrites.
+    pub fn is_replaceable(&self, position: BlockPos) -> bool {
+        self.get_block(position)
+            .is_none_or(|block| block.properties().replaceable)
+    }
+    pub fn get_block(&self, position: BlockPos) -> Option<&Block> {
+        self.chunks.get(&position.chunk())?.get(&position.local())
+    }
+    /// Replaces whatever is at `position`, `None` clears it. Does nothing outside loaded chunks.
+    ///
+    /// The six neighbours are told about the change on the next tick.
+    pub fn set_block(&mut self, position: BlockPos, block: Option<Block>) {
+        let (chunk_pos, block_pos) = (position.chunk(), position.local());
+        let Some(chunk) = self.chunks.get_mut(&chunk_pos) else {
+            return;
+        };
+        match block {
+            Some(block) => chunk.insert(block_pos, block),
+            None => chunk.remove(&block_pos),
+        };
+        self.update_dirty(chunk_pos, block_pos);
+        self.edited.insert(chunk_pos);
+        self.notify_neighbors(position);
     }
 
-    fn update_dirty(&mut self, chunk_pos: Point3<isize>, block_pos: Point3<i8>) {
-        self.dirty.push(chunk_pos);
+    fn update_dirty(&mut self, chunk_pos: ChunkPos, block_pos: LocalPos) {
+        self.dirty.insert(chunk_pos);
 
         let mut x_dirt = 0;
         let mut y_dirt = 0;
         let mut z_dirt = 0;
 
-        if block_pos.x == CHUNK_SIZE as i8 - 1 {
+        if block_pos.x == CHUNK_SIZE as u8 - 1 {
             x_dirt = 1;
-        } else if block_pos.x == 0 as i8 {
+        } else if block_pos.x == 0 {
             x_dirt = -1;
         }
-        if block_pos.y == CHUNK_SIZE as i8 - 1 {
+        if block_pos.y == CHUNK_SIZE as u8 - 1 {
             y_dirt = 1;
-        } else if block_pos.y == 0 as i8 {
+        } else if block_pos.y == 0 {
             y_dirt = -1;
         }
-        if block_pos.z == CHUNK_SIZE as i8 - 1 {
+        if block_pos.z == CHUNK_SIZE as u8 - 1 {
             z_dirt = 1;
-        } else if block_pos.z == 0 as i8 {
+        } else if block_pos.z == 0 {
             z_dirt = -1;
         }
 
         if x_dirt != 0 {
-            self.dirty.push(
+            self.dirty.insert(
                 chunk_pos
                     + Vector3 {
                         x: x_dirt,
                         y: 0,
                         z: 0,
                     },
-            )
+            );
         }
         if y_dirt != 0 {
-            self.dirty.push(
+            self.dirty.insert(
                 chunk_pos
                     + Vector3 {
                         x: 0,
                         y: y_dirt,
                         z: 0,
                     },
-            )
+            );
         }
         if z_dirt != 0 {
-            self.dirty.push(
+            self.dirty.insert(
                 chunk_pos
                     + Vector3 {
                         x: 0,
                         y: 0,
                         z: z_dirt,
                     },
-            )
+            );
         }
 
         if x_dirt != 0 && y_dirt != 0 {
-            self.dirty.push(
+            self.dirty.insert(
                 chunk_pos
                     + Vector3 {
                         x: x_dirt,
                         y: y_dirt,
                         z: 0,
                     },
-            )
+            );
         }
         if x_dirt != 0 && z_dirt != 0 {
-            self.dirty.push(
+            self.dirty.insert(
                 chunk_pos
                     + Vector3 {
                         x: x_dirt,
                         y: 0,
                         z: z_dirt,
                     },
-            )
+            );
         }
         if z_dirt != 0 && y_dirt != 0 {
-            self.dirty.push(
+            self.dirty.insert(
                 chunk_pos
                     + Vector3 {
                         x: 0,
                         y: y_dirt,
                         z: z_dirt,
                     },
-            )
+            );
         }
 
         if x_dirt != 0 && y_dirt != 0 && z_dirt != 0 {
-            self.dirty.push(
+            self.dirty.insert(
                 chunk_pos
                     + Vector3 {
                         x: x_dirt,
                         y: y_dirt,
                         z: z_dirt,
                     },
-            )
+            );
         }
     }
-    pub fn add_block(&mut self, position: Point3<f32>, id: u8) {
-        let (chunk_pos, block_pos) = self.chunk_block_from_global(position);
-
+    pub fn add_block(&mut self, position: BlockPos, id: u8) {
         if !self.block_exists(position) {
-            self.chunks.get_mut(&chunk_pos).unwrap().insert(
-                block_pos,
-                Block {
+            self.set_block(
+                position,
+                Some(Block {
                     block_id: id,
                     block_state: 0,
-                },
+                }),
             );
-            self.update_dirty(chunk_pos, block_pos);
         }
     }
 
-    pub fn remove_block(&mut self, position: Point3<f32>) {
-        let (chunk_pos, block_pos) = self.chunk_block_from_global(position);
-
+    pub fn remove_block(&mut self, position: BlockPos) {
         if self.block_exists(position) {
-            self.chunks.get_mut(&chunk_pos).unwrap().remove(&block_pos);
-            self.update_dirty(chunk_pos, block_pos);
+            self.set_block(position, None);
         }
     }
-
-    fn chunk_block_from_global(&self, position: Point3<f32>) -> (Point3<isize>, Point3<i8>) {
-        let chunk_pos = [
-            (position.x / CHUNK_SIZE as f32).floor() as isize,
-            (position.y / CHUNK_SIZE as f32).floor() as isize,
-            (position.z / CHUNK_SIZE as f32).floor() as isize,
-        ]
-        .into();
-        let block_pos = [
-            (((position.x % CHUNK_SIZE as f32) + CHUNK_SIZE as f32) % CHUNK_SIZE as f32) as i8,
-            (((position.y % CHUNK_SIZE as f32) + CHUNK_SIZE as f32) % CHUNK_SIZE as f32) as i8,
-            (((position.z % CHUNK_SIZE as f32) + CHUNK_SIZE as f32) % CHUNK_SIZE as f32) as i8,
-        ]
-        .into();
-        (chunk_pos, block_pos)
-    }
 }
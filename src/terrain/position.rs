@@ -0,0 +1,231 @@
+use std::ops::{Add, Sub};
+
+use cgmath::{Point3, Vector3};
+
+use super::CHUNK_SIZE;
+
+const SIZE: i32 = CHUNK_SIZE as i32;
+
+/// Position of a block in the world.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockPos {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+/// Position of a chunk, one step is `CHUNK_SIZE` blocks.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+/// Position of a block inside its chunk, every axis is in `0..CHUNK_SIZE`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LocalPos {
+    pub x: u8,
+    pub y: u8,
+    pub z: u8,
+}
+
+impl BlockPos {
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
+    }
+
+    /// The block containing a point in world space.
+    pub fn containing(position: Point3<f32>) -> Self {
+        Self::new(
+            position.x.floor() as i32,
+            position.y.floor() as i32,
+            position.z.floor() as i32,
+        )
+    }
+
+    pub fn chunk(self) -> ChunkPos {
+        ChunkPos::new(
+            self.x.div_euclid(SIZE),
+            self.y.div_euclid(SIZE),
+            self.z.div_euclid(SIZE),
+        )
+    }
+
+    pub fn local(self) -> LocalPos {
+        LocalPos {
+            x: self.x.rem_euclid(SIZE) as u8,
+            y: self.y.rem_euclid(SIZE) as u8,
+            z: self.z.rem_euclid(SIZE) as u8,
+        }
+    }
+
+    /// Corner with the lowest coordinates, the block spans one unit from here.
+    pub fn min_corner(self) -> Point3<f32> {
+        Point3::new(self.x as f32, self.y as f32, self.z as f32)
+    }
+
+    pub fn center(self) -> Point3<f32> {
+        self.min_corner() + Vector3::new(0.5, 0.5, 0.5)
+    }
+
+    /// Middle of the bottom face, where feet standing in this block go.
+    pub fn bottom_center(self) -> Point3<f32> {
+        self.min_corner() + Vector3::new(0.5, 0.0, 0.5)
+    }
+}
+
+impl ChunkPos {
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn block(self, local: LocalPos) -> BlockPos {
+        BlockPos::new(
+            self.x * SIZE + local.x as i32,
+            self.y * SIZE + local.y as i32,
+            self.z * SIZE + local.z as i32,
+        )
+    }
+
+    pub fn min_corner(self) -> Point3<f32> {
+        self.block(LocalPos::new(0, 0, 0)).min_corner()
+    }
+}
+
+impl LocalPos {
+    pub const fn new(x: u8, y: u8, z: u8) -> Self {
+        debug_assert!((x as usize) < CHUNK_SIZE);
+        debug_assert!((y as usize) < CHUNK_SIZE);
+        debug_assert!((z as usize) < CHUNK_SIZE);
+        Self { x, y, z }
+    }
+
+    /// Whole chunk in x, y, z order with x changing fastest.
+    pub fn from_index(index: usize) -> Self {
+        Self::new(
+            (index % CHUNK_SIZE) as u8,
+            (index / CHUNK_SIZE % CHUNK_SIZE) as u8,
+            (index / (CHUNK_SIZE * CHUNK_SIZE)) as u8,
+        )
+    }
+
+    /// Local coordinates may go one step outside the chunk while meshing, so allow negatives.
+    pub fn to_point(self) -> Point3<i8> {
+        Point3::new(self.x as i8, self.y as i8, self.z as i8)
+    }
+
+    /// `None` when the point lies outside the chunk.
+    pub fn from_point(point: Point3<i8>) -> Option<Self> {
+        let inside = |v: i8| (0..CHUNK_SIZE as i8).contains(&v);
+        (inside(point.x) && inside(point.y) && inside(point.z))
+            .then(|| Self::new(point.x as u8, point.y as u8, point.z as u8))
+    }
+}
+
+impl From<[i32; 3]> for BlockPos {
+    fn from([x, y, z]: [i32; 3]) -> Self {
+        Self::new(x, y, z)
+    }
+}
+
+impl From<[i32; 3]> for ChunkPos {
+    fn from([x, y, z]: [i32; 3]) -> Self {
+        Self::new(x, y, z)
+    }
+}
+
+impl Add<Vector3<i32>> for BlockPos {
+    type Output = BlockPos;
+
+    fn add(self, offset: Vector3<i32>) -> BlockPos {
+        BlockPos::new(self.x + offset.x, self.y + offset.y, self.z + offset.z)
+    }
+}
+
+impl Sub<Vector3<i32>> for BlockPos {
+    type Output = BlockPos;
+
+    fn sub(self, offset: Vector3<i32>) -> BlockPos {
+        BlockPos::new(self.x - offset.x, self.y - offset.y, self.z - offset.z)
+    }
+}
+
+impl Sub for BlockPos {
+    type Output = Vector3<i32>;
+
+    fn sub(self, other: BlockPos) -> Vector3<i32> {
+        Vector3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Add<Vector3<i32>> for ChunkPos {
+    type Output = ChunkPos;
+
+    fn add(self, offset: Vector3<i32>) -> ChunkPos {
+        ChunkPos::new(self.x + offset.x, self.y + offset.y, self.z + offset.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    // far enough out that f32 can no longer tell neighbouring blocks apart
+    const FAR: i32 = 1 << 28;
+
+    proptest! {
+        #[test]
+        fn block_round_trips_through_chunk_and_local(x in -FAR..FAR, y in -FAR..FAR, z in -FAR..FAR) {
+            let block = BlockPos::new(x, y, z);
+            prop_assert_eq!(block.chunk().block(block.local()), block);
+        }
+
+        #[test]
+        fn local_stays_inside_the_chunk(x in -FAR..FAR, y in -FAR..FAR, z in -FAR..FAR) {
+            let local = BlockPos::new(x, y, z).local();
+            prop_assert!((local.x as usize) < CHUNK_SIZE);
+            prop_assert!((local.y as usize) < CHUNK_SIZE);
+            prop_assert!((local.z as usize) < CHUNK_SIZE);
+        }
+
+        #[test]
+        fn chunk_and_local_round_trip_through_block(
+            cx in -FAR / SIZE..FAR / SIZE,
+            cy in -FAR / SIZE..FAR / SIZE,
+            cz in -FAR / SIZE..FAR / SIZE,
+            index in 0..CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE,
+        ) {
+            let chunk = ChunkPos::new(cx, cy, cz);
+            let local = LocalPos::from_index(index);
+            let block = chunk.block(local);
+            prop_assert_eq!(block.chunk(), chunk);
+            prop_assert_eq!(block.local(), local);
+        }
+
+        #[test]
+        fn neighbouring_blocks_step_into_neighbouring_chunks(x in -FAR..FAR, y in -FAR..FAR, z in -FAR..FAR) {
+            let block = BlockPos::new(x, y, z);
+            let next = block + Vector3::unit_x();
+            let chunk_step = next.chunk().x - block.chunk().x;
+            prop_assert_eq!(chunk_step, (block.local().x as usize == CHUNK_SIZE - 1) as i32);
+        }
+    }
+
+    #[test]
+    fn negative_blocks_belong_to_negative_chunks() {
+        let block = BlockPos::new(-1, -16, -17);
+        assert_eq!(block.chunk(), ChunkPos::new(-1, -1, -2));
+        assert_eq!(block.local(), LocalPos::new(15, 0, 15));
+    }
+
+    #[test]
+    fn containing_floors_towards_negative_infinity() {
+        assert_eq!(
+            BlockPos::containing(Point3::new(-0.25, 0.0, 15.99)),
+            BlockPos::new(-1, 0, 15)
+        );
+    }
+}
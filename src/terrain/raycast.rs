@@ -0,0 +1,225 @@
+use cgmath::{InnerSpace, Point3, Vector3};
+
+use super::{BlockPos, World};
+use crate::block::Block;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RaycastHit {
+    pub position: BlockPos,
+    /// Normal of the face the ray entered through, zero when the ray starts inside the block.
+    pub normal: Vector3<i32>,
+    /// Distance along the ray to the entry point.
+    pub distance: f32,
+    pub block: Block,
+}
+
+impl World {
+    /// First targetable block along the ray within `max_distance`, fluids are passed through.
+    pub fn raycast(
+        &self,
+        origin: Point3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<RaycastHit> {
+        self.raycast_with(origin, direction, max_distance, |block| {
+            block.properties().targetable
+        })
+    }
+
+    /// Walks every block the ray touches in order (Amanatides & Woo) and returns the first
+    /// one within `max_distance` that `hits` accepts.
+    pub fn raycast_with(
+        &self,
+        origin: Point3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+        hits: impl Fn(&Block) -> bool,
+    ) -> Option<RaycastHit> {
+        if direction.magnitude2() == 0.0 {
+            return None;
+        }
+        let direction = direction.normalize();
+
+        let mut position = BlockPos::containing(origin);
+        let mut normal = Vector3::new(0, 0, 0);
+        let mut distance = 0.0;
+
+        let step = direction.map(|v| if v < 0.0 { -1 } else { 1 });
+        // distance along the ray between two boundaries on each axis
+        let delta = direction.map(|v| (1.0 / v).abs());
+        // distance along the ray to the first boundary on each axis
+        let mut next = Vector3::new(0.0, 0.0, 0.0);
+        let corner = position.min_corner();
+        for axis in 0..3 {
+            let offset = origin[axis] - corner[axis];
+            next[axis] = if direction[axis] == 0.0 {
+                f32::INFINITY
+            } else if direction[axis] < 0.0 {
+                offset * delta[axis]
+            } else {
+                (1.0 - offset) * delta[axis]
+            };
+        }
+
+        loop {
+            if let Some(block) = self.get_block(position).filter(|block| hits(block)) {
+                return Some(RaycastHit {
+                    position,
+                    normal,
+                    distance,
+                    block: *block,
+                });
+            }
+
+            let axis = if next.x < next.y && next.x < next.z {
+                0
+            } else if next.y < next.z {
+                1
+            } else {
+                2
+            };
+            distance = next[axis];
+            if distance > max_distance {
+                return None;
+            }
+            next[axis] += delta[axis];
+            normal = Vector3::new(0, 0, 0);
+            normal[axis] = -step[axis];
+            position = position - normal;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world_with(blocks: &[[i32; 3]]) -> World {
+        World::with_blocks(
+            &blocks
+                .iter()
+                .map(|position| (*position, 3))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{a} != {b}");
+    }
+
+    #[test]
+    fn axis_aligned_rays_hit_the_facing_side() {
+        let world = world_with(&[[5, 0, 0], [-4, 0, 0], [0, 3, 0], [0, 0, -2]]);
+        let origin = Point3::new(0.5, 0.5, 0.5);
+
+        let hit = world.raycast(origin, Vector3::unit_x(), 16.0).unwrap();
+        assert_eq!(hit.position, [5, 0, 0].into());
+        assert_eq!(hit.normal, Vector3::new(-1, 0, 0));
+        assert_close(hit.distance, 4.5);
+
+        let hit = world.raycast(origin, -Vector3::unit_x(), 16.0).unwrap();
+        assert_eq!(hit.position, [-4, 0, 0].into());
+        assert_eq!(hit.normal, Vector3::new(1, 0, 0));
+        assert_close(hit.distance, 3.5);
+
+        let hit = world.raycast(origin, Vector3::unit_y(), 16.0).unwrap();
+        assert_eq!(hit.position, [0, 3, 0].into());
+        assert_eq!(hit.normal, Vector3::new(0, -1, 0));
+        assert_close(hit.distance, 2.5);
+
+        let hit = world.raycast(origin, -Vector3::unit_z(), 16.0).unwrap();
+        assert_eq!(hit.position, [0, 0, -2].into());
+        assert_eq!(hit.normal, Vector3::new(0, 0, 1));
+        assert_close(hit.distance, 1.5);
+    }
+
+    #[test]
+    fn diagonal_ray_visits_blocks_in_order() {
+        // one block beside the exact diagonal and one on it, the side one is closer
+        let world = world_with(&[[3, 2, 0], [3, 3, 0]]);
+
+        let hit = world
+            .raycast(
+                Point3::new(0.5, 0.2, 0.5),
+                Vector3::new(1.0, 1.0, 0.0),
+                16.0,
+            )
+            .unwrap();
+
+        assert_eq!(hit.position, [3, 2, 0].into());
+        assert_eq!(hit.normal, Vector3::new(-1, 0, 0));
+        assert_close(hit.distance, 2.5 * 2f32.sqrt());
+    }
+
+    #[test]
+    fn diagonal_ray_enters_through_the_top() {
+        let world = world_with(&[[2, -3, 2]]);
+
+        let hit = world
+            .raycast(
+                Point3::new(0.6, 0.9, 0.7),
+                Vector3::new(1.0, -2.0, 1.0),
+                16.0,
+            )
+            .unwrap();
+
+        assert_eq!(hit.position, [2, -3, 2].into());
+        assert_eq!(hit.normal, Vector3::new(0, 1, 0));
+        assert_close(hit.distance, 1.45 * 6f32.sqrt());
+    }
+
+    #[test]
+    fn ray_crosses_chunk_boundaries() {
+        let world = world_with(&[[17, 5, 5], [-16, 5, 5]]);
+
+        let hit = world
+            .raycast(Point3::new(14.5, 5.5, 5.5), Vector3::unit_x(), 16.0)
+            .unwrap();
+        assert_eq!(hit.position, [17, 5, 5].into());
+        assert_close(hit.distance, 2.5);
+
+        let hit = world
+            .raycast(Point3::new(1.5, 5.5, 5.5), -Vector3::unit_x(), 32.0)
+            .unwrap();
+        assert_eq!(hit.position, [-16, 5, 5].into());
+        assert_eq!(hit.normal, Vector3::new(1, 0, 0));
+        assert_close(hit.distance, 16.5);
+    }
+
+    #[test]
+    fn ray_stops_at_max_distance() {
+        let world = world_with(&[[5, 0, 0]]);
+
+        assert!(world
+            .raycast(Point3::new(0.5, 0.5, 0.5), Vector3::unit_x(), 4.4)
+            .is_none());
+    }
+
+    #[test]
+    fn ray_starting_inside_a_block_hits_it() {
+        let world = world_with(&[[0, 0, 0]]);
+
+        let hit = world
+            .raycast(Point3::new(0.5, 0.5, 0.5), Vector3::unit_x(), 16.0)
+            .unwrap();
+
+        assert_eq!(hit.position, [0, 0, 0].into());
+        assert_eq!(hit.normal, Vector3::new(0, 0, 0));
+        assert_close(hit.distance, 0.0);
+    }
+
+    #[test]
+    fn ray_passes_through_water_by_default() {
+        let world = World::with_blocks(&[([2, 0, 0], 0), ([3, 0, 0], 0), ([4, 0, 0], 3)]);
+        let origin = Point3::new(0.5, 0.5, 0.5);
+
+        let hit = world.raycast(origin, Vector3::unit_x(), 16.0).unwrap();
+        assert_eq!(hit.position, [4, 0, 0].into());
+
+        let hit = world
+            .raycast_with(origin, Vector3::unit_x(), 16.0, |_| true)
+            .unwrap();
+        assert_eq!(hit.position, [2, 0, 0].into());
+        assert_eq!(hit.block.block_id, 0);
+    }
+}
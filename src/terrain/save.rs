@@ -0,0 +1,447 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{ErrorKind, Read, Write},
+    path::Path,
+};
+
+use anyhow::{anyhow, bail, Result};
+use cgmath::Vector3;
+
+use super::{
+    update::ScheduledTick, BlockPos, Chunk, ChunkPos, Generator, LocalPos, World, CHUNK_SIZE,
+};
+use crate::{
+    block::Block,
+    entity::{Entity, EntityId},
+};
+
+/// Version 2 adds the next entity id and writes positions as i32, entities are kept per chunk
+/// in their own files. Version 3 adds the spawn point.
+const VERSION: u32 = 3;
+
+// Only chunks that were edited are written, everything else is regenerated from the seed.
+// Entities are written per chunk, for every chunk that has any.
+impl World {
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir.join("chunks"))?;
+
+        let mut data = Vec::new();
+        data.write_all(&VERSION.to_le_bytes())?;
+        data.write_all(&self.game_tick.to_le_bytes())?;
+        data.write_all(&self.entities.next_id().to_le_bytes())?;
+        write_position(&mut data, self.spawn_point)?;
+        data.write_all(&(self.scheduled.len() as u32).to_le_bytes())?;
+        for tick in &self.scheduled {
+            write_position(&mut data, tick.position)?;
+            data.write_all(&[tick.block_id])?;
+            data.write_all(&tick.due.to_le_bytes())?;
+        }
+        fs::write(dir.join("world.dat"), data)?;
+
+        for chunk_pos in &self.edited {
+            if let Some(chunk) = self.chunks.get(chunk_pos) {
+                save_blocks(dir, *chunk_pos, chunk)?;
+            }
+        }
+
+        let mut by_chunk: BTreeMap<ChunkPos, Vec<(EntityId, &Entity)>> = BTreeMap::new();
+        for (id, entity) in self.entities.iter() {
+            by_chunk
+                .entry(entity.chunk())
+                .or_default()
+                .push((id, entity));
+        }
+        // files of loaded chunks whose entities are all gone are stale
+        for (chunk_pos, path) in saved_chunks(&dir.join("entities"))? {
+            if self.chunks.contains_key(&chunk_pos) && !by_chunk.contains_key(&chunk_pos) {
+                fs::remove_file(path)?;
+            }
+        }
+        for (chunk_pos, entities) in &by_chunk {
+            save_entities(dir, *chunk_pos, entities)?;
+        }
+        Ok(())
+    }
+
+    /// Saves a chunk and the entities in it, then forgets about both.
+    pub fn unload_chunk(&mut self, chunk_pos: ChunkPos, dir: &Path) -> Result<()> {
+        let Some(chunk) = self.chunks.remove(&chunk_pos) else {
+            return Ok(());
+        };
+        if self.edited.remove(&chunk_pos) {
+            save_blocks(dir, chunk_pos, &chunk)?;
+        }
+        let entities = self.entities.remove_chunk(chunk_pos);
+        let entities: Vec<(EntityId, &Entity)> =
+            entities.iter().map(|(id, entity)| (*id, entity)).collect();
+        save_entities(dir, chunk_pos, &entities)?;
+        self.meshes.remove(&chunk_pos);
+        self.mark_neighbors_dirty(chunk_pos);
+        Ok(())
+    }
+
+    /// Brings a chunk back as it was saved, or fresh from the generator if it never was,
+    /// along with its entities.
+    pub fn load_chunk(
+        &mut self,
+        chunk_pos: ChunkPos,
+        generator: &impl Generator,
+        dir: &Path,
+    ) -> Result<()> {
+        let path = dir.join("chunks").join(chunk_file_name(chunk_pos));
+        let chunk = match fs::read(&path) {
+            Ok(data) => {
+                self.edited.insert(chunk_pos);
+                read_blocks(&data)?
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => generator.generate(chunk_pos),
+            Err(e) => bail!(e),
+        };
+        self.chunks.insert(chunk_pos, chunk);
+        self.load_entities(dir, chunk_pos)?;
+        self.mark_neighbors_dirty(chunk_pos);
+        Ok(())
+    }
+
+    fn load_entities(&mut self, dir: &Path, chunk_pos: ChunkPos) -> Result<()> {
+        let path = dir.join("entities").join(chunk_file_name(chunk_pos));
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => bail!(e),
+        };
+        let mut data = data.as_slice();
+        for _ in 0..read_u32(&mut data)? {
+            let id = EntityId(read_u64(&mut data)?);
+            let entity = Entity::read(&mut data)?;
+            self.entities.insert(id, entity);
+        }
+        Ok(())
+    }
+
+    /// The chunk and everything around it, their ambient occlusion looks into it.
+    fn mark_neighbors_dirty(&mut self, chunk_pos: ChunkPos) {
+        for x in -1..=1 {
+            for y in -1..=1 {
+                for z in -1..=1 {
+                    self.dirty.insert(chunk_pos + Vector3 { x, y, z });
+                }
+            }
+        }
+    }
+
+    /// Replaces generated chunks with the saved ones. A missing save is not an error.
+    pub fn load(&mut self, dir: &Path) -> Result<()> {
+        let Ok(data) = fs::read(dir.join("world.dat")) else {
+            return Ok(());
+        };
+        let mut data = data.as_slice();
+        let version = read_u32(&mut data)?;
+        if !(1..=VERSION).contains(&version) {
+            bail!("unsupported world save version {version}");
+        }
+        self.game_tick = read_u64(&mut data)?;
+        if version >= 2 {
+            self.entities.set_next_id(read_u64(&mut data)?);
+        }
+        // older saves keep the spawn point found for the freshly generated world
+        if version >= 3 {
+            self.spawn_point = read_position(&mut data, version)?;
+        }
+        self.scheduled.clear();
+        for _ in 0..read_u32(&mut data)? {
+            let position = read_position(&mut data, version)?;
+            let block_id = read_u8(&mut data)?;
+            let due = read_u64(&mut data)?;
+            self.scheduled.push(ScheduledTick {
+                position,
+                block_id,
+                due,
+            });
+        }
+
+        for (chunk_pos, path) in saved_chunks(&dir.join("chunks"))? {
+            let chunk = read_blocks(&fs::read(&path)?)?;
+            self.chunks.insert(chunk_pos, chunk);
+            self.edited.insert(chunk_pos);
+            self.mark_neighbors_dirty(chunk_pos);
+        }
+        // entities of chunks that are not loaded wait in their files until they are
+        for (chunk_pos, _) in saved_chunks(&dir.join("entities"))? {
+            if self.chunks.contains_key(&chunk_pos) {
+                self.load_entities(dir, chunk_pos)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every chunk file in `dir` and the chunk it is for. A missing directory has none.
+fn saved_chunks(dir: &Path) -> Result<Vec<(ChunkPos, std::path::PathBuf)>> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    let mut chunks = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let chunk_pos = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(parse_chunk_file_name);
+        if let Some(chunk_pos) = chunk_pos {
+            chunks.push((chunk_pos, path));
+        }
+    }
+    Ok(chunks)
+}
+
+fn save_blocks(dir: &Path, chunk_pos: ChunkPos, chunk: &Chunk) -> Result<()> {
+    fs::create_dir_all(dir.join("chunks"))?;
+    let mut data = Vec::new();
+    data.write_all(&(chunk.len() as u32).to_le_bytes())?;
+    for (position, block) in chunk {
+        data.write_all(&[
+            position.x,
+            position.y,
+            position.z,
+            block.block_id,
+            block.block_state,
+        ])?;
+    }
+    fs::write(dir.join("chunks").join(chunk_file_name(chunk_pos)), data)?;
+    Ok(())
+}
+
+fn read_blocks(data: &[u8]) -> Result<Chunk> {
+    let mut data = data;
+    let mut chunk = Chunk::new();
+    for _ in 0..read_u32(&mut data)? {
+        let mut bytes = [0; 5];
+        data.read_exact(&mut bytes)?;
+        if bytes[..3].iter().any(|&value| value as usize >= CHUNK_SIZE) {
+            bail!(
+                "block at {:?} is outside its chunk",
+                [bytes[0], bytes[1], bytes[2]]
+            );
+        }
+        chunk.insert(
+            LocalPos::new(bytes[0], bytes[1], bytes[2]),
+            Block {
+                block_id: bytes[3],
+                block_state: bytes[4],
+            },
+        );
+    }
+    Ok(chunk)
+}
+
+/// Writes the entities of a chunk, or removes its file when it has none left.
+fn save_entities(dir: &Path, chunk_pos: ChunkPos, entities: &[(EntityId, &Entity)]) -> Result<()> {
+    let path = dir.join("entities").join(chunk_file_name(chunk_pos));
+    if entities.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => bail!(e),
+            _ => Ok(()),
+        };
+    }
+    fs::create_dir_all(dir.join("entities"))?;
+    let mut data = Vec::new();
+    data.write_all(&(entities.len() as u32).to_le_bytes())?;
+    for (id, entity) in entities {
+        data.write_all(&id.0.to_le_bytes())?;
+        entity.write(&mut data)?;
+    }
+    fs::write(path, data)?;
+    Ok(())
+}
+
+fn chunk_file_name(chunk_pos: ChunkPos) -> String {
+    format!("{}_{}_{}.dat", chunk_pos.x, chunk_pos.y, chunk_pos.z)
+}
+
+fn parse_chunk_file_name(stem: &str) -> Option<ChunkPos> {
+    let mut parts = stem.split('_').map(|part| part.parse::<i32>().ok());
+    let position = [parts.next()??, parts.next()??, parts.next()??];
+    parts.next().is_none().then(|| position.into())
+}
+
+fn write_position(data: &mut Vec<u8>, position: BlockPos) -> Result<()> {
+    for value in [position.x, position.y, position.z] {
+        data.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Version 1 wrote positions as i64, later versions as i32.
+fn read_position(data: &mut &[u8], version: u32) -> Result<BlockPos> {
+    let mut position = [0; 3];
+    for value in &mut position {
+        *value = if version == 1 {
+            let wide = read_u64(data)? as i64;
+            i32::try_from(wide).map_err(|_| anyhow!("position {wide} is out of range"))?
+        } else {
+            read_u32(data)? as i32
+        };
+    }
+    Ok(position.into())
+}
+
+pub(crate) fn read_u8(data: &mut &[u8]) -> Result<u8> {
+    let mut bytes = [0; 1];
+    data.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+pub(crate) fn read_u32(data: &mut &[u8]) -> Result<u32> {
+    let mut bytes = [0; 4];
+    data.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+pub(crate) fn read_u64(data: &mut &[u8]) -> Result<u64> {
+    let mut bytes = [0; 8];
+    data.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+pub(crate) fn read_f32(data: &mut &[u8]) -> Result<f32> {
+    let mut bytes = [0; 4];
+    data.read_exact(&mut bytes)?;
+    Ok(f32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        entity::item,
+        player::inventory::ItemStack,
+        terrain::{spawn, FlatGenerator},
+    };
+
+    #[test]
+    fn edited_chunks_and_scheduled_ticks_survive_a_save() {
+        let dir = std::env::temp_dir().join(format!("game-wgpu-save-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut chunks = HashMap::new();
+        chunks.insert([0, 0, 0].into(), HashMap::new());
+        chunks.insert([-1, 0, 0].into(), HashMap::new());
+        let mut world = World::from_chunks(chunks.clone(), 0);
+        world.set_block(
+            [-3, 2, 7].into(),
+            Some(Block {
+                block_id: 4,
+                block_state: 2,
+            }),
+        );
+        world.game_tick = 40;
+        world.spawn_point = [-8, 3, 12].into();
+        world.schedule_tick([-3, 2, 7].into(), 5);
+
+        world.save(&dir).unwrap();
+        let mut loaded = World::from_chunks(chunks, 0);
+        loaded.load(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.chunks, world.chunks);
+        assert_eq!(loaded.game_tick, 40);
+        assert_eq!(loaded.scheduled_ticks(), world.scheduled_ticks());
+        assert_eq!(loaded.spawn_point, world.spawn_point);
+    }
+
+    #[test]
+    fn entities_survive_a_save_with_their_ids() {
+        let dir = std::env::temp_dir().join(format!("game-wgpu-entities-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut world = World::with_blocks(&[]);
+        let stack = ItemStack { item: 2, count: 3 };
+        world
+            .entities
+            .spawn(item::new([0.5, 1.0, 0.5].into(), stack));
+        let id = world.drop_item([-10, 4, 3].into(), stack);
+        world.save(&dir).unwrap();
+
+        let mut loaded = World::with_blocks(&[]);
+        loaded.load(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.entities, world.entities);
+        assert_eq!(
+            loaded
+                .entities
+                .spawn(item::new([0.5, 1.0, 0.5].into(), stack)),
+            EntityId(id.0 + 1)
+        );
+    }
+
+    #[test]
+    fn unloaded_chunks_come_back_with_their_blocks_and_entities() {
+        let dir = std::env::temp_dir().join(format!("game-wgpu-unload-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut world = World::with_blocks(&[([3, 2, 1], 4)]);
+        let stack = ItemStack { item: 2, count: 3 };
+        let here = world.drop_item([3, 3, 1].into(), stack);
+        let elsewhere = world.drop_item([-3, 3, 1].into(), stack);
+        let before = world.entities.get(here).unwrap().clone();
+
+        world.unload_chunk([0, 0, 0].into(), &dir).unwrap();
+
+        assert!(world.get_chunk([0, 0, 0].into()).is_none());
+        assert!(world.entities.get(here).is_none());
+        assert!(world.entities.get(elsewhere).is_some());
+
+        world
+            .load_chunk([0, 0, 0].into(), &FlatGenerator { layers: vec![] }, &dir)
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(world.get_block([3, 2, 1].into()).unwrap().block_id, 4);
+        assert_eq!(world.entities.get(here), Some(&before));
+        assert_eq!(world.entities.len(), 2);
+    }
+
+    #[test]
+    fn blocks_outside_their_chunk_are_rejected() {
+        let mut data = 1u32.to_le_bytes().to_vec();
+        data.extend([0, CHUNK_SIZE as u8, 0, 2, 0]);
+
+        assert!(read_blocks(&data).is_err());
+        data[5] = CHUNK_SIZE as u8 - 1;
+        assert!(read_blocks(&data).is_ok());
+    }
+
+    #[test]
+    fn version_1_positions_are_read_as_i64() {
+        let dir = std::env::temp_dir().join(format!("game-wgpu-v1-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut data = Vec::new();
+        data.extend(1u32.to_le_bytes());
+        data.extend(40u64.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        for value in [-3i64, 2, 7] {
+            data.extend(value.to_le_bytes());
+        }
+        data.push(5);
+        data.extend(41u64.to_le_bytes());
+        fs::write(dir.join("world.dat"), data).unwrap();
+
+        let mut world = World::with_blocks(&[]);
+        world.load(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(world.game_tick, 40);
+        assert_eq!(world.spawn_point, spawn::FALLBACK_SPAWN);
+        assert_eq!(
+            world.scheduled_ticks(),
+            &[ScheduledTick {
+                position: [-3, 2, 7].into(),
+                block_id: 5,
+                due: 41,
+            }]
+        );
+    }
+}
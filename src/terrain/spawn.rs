@@ -0,0 +1,142 @@
+use cgmath::Vector3;
+
+use super::{BlockPos, World, CHUNK_SIZE};
+
+/// Free blocks needed above the ground, the player is 2.8 blocks tall.
+pub const SPAWN_HEADROOM: i32 = 3;
+/// Used when no column near the origin is safe.
+pub const FALLBACK_SPAWN: BlockPos = BlockPos::new(5, 16, 5);
+/// Columns further than this from the origin are not considered.
+const SEARCH_RADIUS: i32 = 64;
+
+impl World {
+    /// The column nearest the origin with solid, non-fluid ground and `headroom` free blocks
+    /// above it. Returns the block the player's feet go in, the first one above the ground.
+    pub fn find_spawn(&self, headroom: i32) -> Option<BlockPos> {
+        let bottom = self.chunks.keys().map(|chunk| chunk.y).min()? * CHUNK_SIZE as i32;
+        let top = (self.chunks.keys().map(|chunk| chunk.y).max()? + 1) * CHUNK_SIZE as i32 - 1;
+
+        let mut columns: Vec<(i32, i32)> = (-SEARCH_RADIUS..=SEARCH_RADIUS)
+            .flat_map(|x| (-SEARCH_RADIUS..=SEARCH_RADIUS).map(move |z| (x, z)))
+            .collect();
+        columns.sort_by_key(|&(x, z)| (x * x + z * z, x, z));
+
+        columns.into_iter().find_map(|(x, z)| {
+            (bottom..=top)
+                .rev()
+                .map(|y| BlockPos::new(x, y, z))
+                .find(|ground| self.can_stand_on(*ground, headroom))
+                .map(|ground| ground + Vector3::unit_y())
+        })
+    }
+
+    fn can_stand_on(&self, ground: BlockPos, headroom: i32) -> bool {
+        let free = |position: BlockPos| {
+            self.get_chunk(position.chunk()).is_some()
+                && !self.is_solid(position)
+                && !self.is_fluid(position)
+        };
+        self.is_solid(ground)
+            && !self.is_fluid(ground)
+            && (1..=headroom).all(|dy| free(ground + Vector3::new(0, dy, 0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        block::Block,
+        terrain::{FlatGenerator, NoiseGenerator},
+    };
+
+    fn flat(layers: &[u8]) -> World {
+        World::generate(
+            &FlatGenerator {
+                layers: layers.to_vec(),
+            },
+            1,
+            0,
+        )
+    }
+
+    fn stone() -> Option<Block> {
+        Some(Block {
+            block_id: 3,
+            block_state: 0,
+        })
+    }
+
+    #[test]
+    fn flat_world_spawns_at_the_origin_on_top() {
+        let world = flat(&[3, 2, 1]);
+
+        assert_eq!(world.spawn_point, BlockPos::new(0, 3, 0));
+    }
+
+    #[test]
+    fn blocked_origin_moves_to_the_nearest_column() {
+        let mut world = flat(&[3, 2, 1]);
+        // a pillar up to the top of the loaded world leaves no room anywhere in the column
+        for y in 3..2 * CHUNK_SIZE as i32 {
+            world.set_block([0, y, 0].into(), stone());
+        }
+
+        assert_eq!(world.find_spawn(SPAWN_HEADROOM), Some([-1, 3, 0].into()));
+    }
+
+    #[test]
+    fn low_ceiling_needs_enough_headroom() {
+        let mut world = flat(&[3, 2, 1]);
+        // two free blocks above the ground, then solid to the top of the loaded world
+        for y in 5..2 * CHUNK_SIZE as i32 {
+            world.set_block([0, y, 0].into(), stone());
+        }
+
+        assert_eq!(world.find_spawn(3), Some([-1, 3, 0].into()));
+        assert_eq!(world.find_spawn(2), Some([0, 3, 0].into()));
+    }
+
+    #[test]
+    fn ocean_is_skipped_for_the_nearest_island() {
+        let mut world = flat(&[3, 0, 0]);
+        world.set_block([4, 1, 3].into(), stone());
+        world.set_block([4, 2, 3].into(), stone());
+
+        assert_eq!(world.find_spawn(SPAWN_HEADROOM), Some([4, 3, 3].into()));
+    }
+
+    #[test]
+    fn nothing_to_stand_on_finds_nothing() {
+        let world = flat(&[0, 0]);
+
+        assert_eq!(world.find_spawn(SPAWN_HEADROOM), None);
+        assert_eq!(world.spawn_point, FALLBACK_SPAWN);
+    }
+
+    #[test]
+    fn noise_world_spawns_on_dry_ground() {
+        let world = World::generate(&NoiseGenerator::new(696969), 1, 0);
+        let spawn = world.spawn_point;
+
+        assert_ne!(spawn, FALLBACK_SPAWN);
+        let ground = spawn - Vector3::unit_y();
+        assert!(world.is_solid(ground));
+        assert!(!world.is_fluid(ground));
+        for dy in 0..SPAWN_HEADROOM {
+            let above = spawn + Vector3::new(0, dy, 0);
+            assert!(world.get_block(above).is_none(), "{above:?} is taken");
+        }
+        // no nearer column qualifies
+        let distance = spawn.x * spawn.x + spawn.z * spawn.z;
+        for x in -SEARCH_RADIUS..=SEARCH_RADIUS {
+            for z in -SEARCH_RADIUS..=SEARCH_RADIUS {
+                if x * x + z * z < distance {
+                    for y in -(CHUNK_SIZE as i32)..2 * CHUNK_SIZE as i32 {
+                        assert!(!world.can_stand_on([x, y, z].into(), SPAWN_HEADROOM));
+                    }
+                }
+            }
+        }
+    }
+}
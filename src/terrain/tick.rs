@@ -0,0 +1,315 @@
+use cgmath::Vector3;
+
+use super::{BlockPos, ChunkPos, LocalPos, World, CHUNK_SIZE};
+use crate::block::{self, Block};
+
+const GRASS: u8 = 1;
+const DIRT: u8 = 2;
+const LOG: u8 = 10;
+const LEAVES: u8 = 11;
+
+/// How far leaves can be from a log, walking through other leaves, before they decay.
+const LEAF_SUPPORT_DISTANCE: usize = 4;
+
+/// Small seedable generator (splitmix64) so random ticks can be replayed in tests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickRng {
+    state: u64,
+}
+
+impl TickRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Seed that carries on from here, for saving.
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform-ish number in `0..n`, the modulo bias is irrelevant for ticking.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+impl World {
+    /// Advances the world by one game tick: due scheduled ticks first, then neighbour
+    /// updates, then random ticks, then entities.
+    pub fn tick(&mut self) {
+        self.game_tick += 1;
+        self.run_scheduled_ticks();
+        self.process_block_updates();
+        self.random_tick();
+        self.tick_entities();
+    }
+
+    /// Picks `random_tick_speed` random blocks in every loaded chunk and lets them react.
+    pub fn random_tick(&mut self) {
+        // HashMap order changes between runs, sort to keep a seeded world deterministic
+        let mut chunk_positions: Vec<ChunkPos> = self.chunks.keys().copied().collect();
+        chunk_positions.sort();
+
+        let volume = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as u64;
+        for chunk_pos in chunk_positions {
+            for _ in 0..self.random_tick_speed {
+                let index = self.rng.below(volume) as usize;
+                let position = chunk_pos.block(LocalPos::from_index(index));
+                if let Some(block) = self.get_block(position).copied() {
+                    self.tick_block(position, block);
+                }
+            }
+        }
+    }
+
+    fn tick_block(&mut self, position: BlockPos, block: Block) {
+        match block.block_id {
+            GRASS => self.tick_grass(position),
+            LEAVES => self.tick_leaves(position),
+            _ => {}
+        }
+    }
+
+    fn tick_grass(&mut self, position: BlockPos) {
+        if !self.lets_light_through(position + Vector3::unit_y()) {
+            self.set_block(
+                position,
+                Some(Block {
+                    block_id: DIRT,
+                    block_state: 0,
+                }),
+            );
+            return;
+        }
+
+        let target = position
+            + Vector3 {
+                x: self.rng.below(3) as i32 - 1,
+                y: self.rng.below(5) as i32 - 3,
+                z: self.rng.below(3) as i32 - 1,
+            };
+        let is_dirt = self
+            .get_block(target)
+            .is_some_and(|block| block.block_id == DIRT);
+        if is_dirt && self.lets_light_through(target + Vector3::unit_y()) {
+            self.set_block(
+                target,
+                Some(Block {
+                    block_id: GRASS,
+                    block_state: 0,
+                }),
+            );
+        }
+    }
+
+    fn tick_leaves(&mut self, position: BlockPos) {
+        if !self.leaves_supported(position) {
+            self.set_block(position, None);
+        }
+    }
+
+    /// Breadth first search through connected leaves looking for a log.
+    fn leaves_supported(&self, start: BlockPos) -> bool {
+        let mut visited = vec![start];
+        let mut frontier = vec![start];
+        for _ in 0..LEAF_SUPPORT_DISTANCE {
+            let mut next = Vec::new();
+            for position in frontier {
+                for offset in NEIGHBORS {
+                    let neighbor = position + Vector3::from(offset);
+                    match self.get_block(neighbor).map(|block| block.block_id) {
+                        Some(LOG) => return true,
+                        Some(LEAVES) if !visited.contains(&neighbor) => {
+                            visited.push(neighbor);
+                            next.push(neighbor);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            frontier = next;
+        }
+        false
+    }
+
+    /// Water is see-through but still smothers grass.
+    fn lets_light_through(&self, position: BlockPos) -> bool {
+        self.get_block(position)
+            .is_none_or(|block| block.block_id != 0 && block::is_transparent(block.block_id))
+    }
+}
+
+pub(super) const NEIGHBORS: [[i32; 3]; 6] = [
+    [1, 0, 0],
+    [-1, 0, 0],
+    [0, 1, 0],
+    [0, -1, 0],
+    [0, 0, 1],
+    [0, 0, -1],
+];
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn world_with(blocks: &[([i32; 3], u8)], seed: u64) -> World {
+        let mut chunks = HashMap::new();
+        chunks.insert([0, 0, 0].into(), HashMap::new());
+        let mut world = World::from_chunks(chunks, seed);
+        for (position, block_id) in blocks {
+            world.set_block(
+                (*position).into(),
+                Some(Block {
+                    block_id: *block_id,
+                    block_state: 0,
+                }),
+            );
+        }
+        world
+    }
+
+    fn id_at(world: &World, position: [i32; 3]) -> Option<u8> {
+        world.get_block(position.into()).map(|block| block.block_id)
+    }
+
+    #[test]
+    fn grass_spreads_onto_lit_dirt() {
+        let mut blocks: Vec<([i32; 3], u8)> = Vec::new();
+        for x in 4..=6 {
+            for z in 4..=6 {
+                blocks.push(([x, 4, z], DIRT));
+            }
+        }
+        blocks.push(([5, 4, 5], GRASS));
+        let mut world = world_with(&blocks, 1);
+
+        for _ in 0..1000 {
+            world.tick_block(
+                [5, 4, 5].into(),
+                world.get_block([5, 4, 5].into()).copied().unwrap(),
+            );
+        }
+
+        for x in 4..=6 {
+            for z in 4..=6 {
+                assert_eq!(id_at(&world, [x, 4, z]), Some(GRASS));
+            }
+        }
+    }
+
+    #[test]
+    fn grass_does_not_spread_onto_covered_dirt() {
+        let mut world = world_with(&[([5, 4, 5], GRASS), ([6, 4, 5], DIRT), ([6, 5, 5], 3)], 2);
+
+        for _ in 0..200 {
+            world.tick_block(
+                [5, 4, 5].into(),
+                world.get_block([5, 4, 5].into()).copied().unwrap(),
+            );
+        }
+
+        assert_eq!(id_at(&world, [6, 4, 5]), Some(DIRT));
+    }
+
+    #[test]
+    fn covered_grass_reverts_to_dirt() {
+        let mut world = world_with(&[([5, 4, 5], GRASS), ([5, 5, 5], 3)], 3);
+
+        world.tick_block(
+            [5, 4, 5].into(),
+            world.get_block([5, 4, 5].into()).copied().unwrap(),
+        );
+
+        assert_eq!(id_at(&world, [5, 4, 5]), Some(DIRT));
+    }
+
+    #[test]
+    fn grass_under_glass_survives() {
+        let mut world = world_with(&[([5, 4, 5], GRASS), ([5, 5, 5], 9)], 4);
+
+        world.tick_block(
+            [5, 4, 5].into(),
+            world.get_block([5, 4, 5].into()).copied().unwrap(),
+        );
+
+        assert_eq!(id_at(&world, [5, 4, 5]), Some(GRASS));
+    }
+
+    #[test]
+    fn leaves_near_log_stay() {
+        let mut world = world_with(
+            &[
+                ([5, 4, 5], LOG),
+                ([5, 5, 5], LEAVES),
+                ([5, 6, 5], LEAVES),
+                ([5, 7, 5], LEAVES),
+            ],
+            5,
+        );
+
+        world.tick_block(
+            [5, 7, 5].into(),
+            world.get_block([5, 7, 5].into()).copied().unwrap(),
+        );
+
+        assert_eq!(id_at(&world, [5, 7, 5]), Some(LEAVES));
+    }
+
+    #[test]
+    fn unsupported_leaves_decay() {
+        let mut blocks = vec![([5, 0, 5], LOG)];
+        for y in 1..=6 {
+            blocks.push(([5, y, 5], LEAVES));
+        }
+        let mut world = world_with(&blocks, 6);
+
+        world.tick_block(
+            [5, 6, 5].into(),
+            world.get_block([5, 6, 5].into()).copied().unwrap(),
+        );
+        world.tick_block(
+            [5, 2, 5].into(),
+            world.get_block([5, 2, 5].into()).copied().unwrap(),
+        );
+
+        assert_eq!(id_at(&world, [5, 6, 5]), None);
+        assert_eq!(id_at(&world, [5, 2, 5]), Some(LEAVES));
+    }
+
+    #[test]
+    fn random_ticks_are_deterministic_for_a_seed() {
+        let mut blocks: Vec<([i32; 3], u8)> = Vec::new();
+        for x in 0..CHUNK_SIZE as i32 {
+            for z in 0..CHUNK_SIZE as i32 {
+                blocks.push(([x, 4, z], DIRT));
+            }
+        }
+        blocks.push(([8, 4, 8], GRASS));
+        let mut a = world_with(&blocks, 42);
+        let mut b = world_with(&blocks, 42);
+        a.random_tick_speed = 256;
+        b.random_tick_speed = 256;
+
+        for _ in 0..1000 {
+            a.random_tick();
+            b.random_tick();
+        }
+
+        assert_eq!(a.chunks, b.chunks);
+        let grass = a.chunks[&[0, 0, 0].into()]
+            .values()
+            .filter(|block| block.block_id == GRASS)
+            .count();
+        assert!(grass > 1);
+    }
+}
@@ -0,0 +1,233 @@
+use std::mem;
+
+use cgmath::Vector3;
+
+use super::{BlockPos, World};
+use crate::block::{self, Block, Model};
+
+const SAND: u8 = 4;
+
+/// Upper bound on neighbour updates handled in one tick. Blocks that keep changing each
+/// other would otherwise never let the tick finish, the rest carries over to the next tick.
+pub const MAX_BLOCK_UPDATES_PER_TICK: usize = 4096;
+
+/// A block that asked to be ticked again at `due`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScheduledTick {
+    pub position: BlockPos,
+    /// The tick is dropped if the block was replaced in the meantime.
+    pub block_id: u8,
+    pub due: u64,
+}
+
+impl World {
+    /// Ticks the block at `position` again in `delay` ticks, at least one tick from now.
+    pub fn schedule_tick(&mut self, position: BlockPos, delay: u64) {
+        let Some(block) = self.get_block(position) else {
+            return;
+        };
+        let tick = ScheduledTick {
+            position,
+            block_id: block.block_id,
+            due: self.game_tick + delay.max(1),
+        };
+        let already_scheduled = self
+            .scheduled
+            .iter()
+            .any(|other| other.position == tick.position && other.block_id == tick.block_id);
+        if !already_scheduled {
+            self.scheduled.push(tick);
+        }
+    }
+
+    pub fn scheduled_ticks(&self) -> &[ScheduledTick] {
+        &self.scheduled
+    }
+
+    pub(super) fn notify_neighbors(&mut self, position: BlockPos) {
+        for offset in super::tick::NEIGHBORS {
+            self.pending_updates
+                .push_back(position + Vector3::from(offset));
+        }
+    }
+
+    pub(super) fn run_scheduled_ticks(&mut self) {
+        let game_tick = self.game_tick;
+        let (due, later): (Vec<_>, Vec<_>) = mem::take(&mut self.scheduled)
+            .into_iter()
+            .partition(|tick| tick.due <= game_tick);
+        self.scheduled = later;
+
+        for tick in due {
+            let Some(block) = self.get_block(tick.position).copied() else {
+                continue;
+            };
+            if block.block_id == tick.block_id {
+                self.scheduled_tick(tick.position, block);
+            }
+        }
+    }
+
+    pub(super) fn process_block_updates(&mut self) {
+        for _ in 0..MAX_BLOCK_UPDATES_PER_TICK {
+            let Some(position) = self.pending_updates.pop_front() else {
+                return;
+            };
+            if let Some(block) = self.get_block(position).copied() {
+                self.neighbor_changed(position, block);
+            }
+        }
+    }
+
+    fn neighbor_changed(&mut self, position: BlockPos, block: Block) {
+        if block.block_id == SAND {
+            self.schedule_tick(position, 2);
+        }
+        // Ladders and vines drop off when the wall they hang on goes
+        if block.properties().model == Model::Wall {
+            let wall = position + block::wall_direction(block.block_state);
+            if !self.is_solid(wall) && self.chunks.contains_key(&wall.chunk()) {
+                self.set_block(position, None);
+            }
+        }
+    }
+
+    fn scheduled_tick(&mut self, position: BlockPos, block: Block) {
+        if block.block_id == SAND {
+            let below = position - Vector3::unit_y();
+            let supported = self
+                .get_block(below)
+                .is_some_and(|block| block.block_id != 0);
+            if !supported && self.chunks.contains_key(&below.chunk()) {
+                self.set_block(position, None);
+                self.set_block(below, Some(block));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn world_with(blocks: &[([i32; 3], u8)]) -> World {
+        let mut chunks = HashMap::new();
+        chunks.insert([0, 0, 0].into(), HashMap::new());
+        let mut world = World::from_chunks(chunks, 0);
+        for (position, block_id) in blocks {
+            world.set_block(
+                (*position).into(),
+                Some(Block {
+                    block_id: *block_id,
+                    block_state: 0,
+                }),
+            );
+        }
+        world.random_tick_speed = 0;
+        world
+    }
+
+    #[test]
+    fn setting_a_block_notifies_all_six_neighbours() {
+        let mut world = world_with(&[]);
+
+        world.set_block(
+            [5, 5, 5].into(),
+            Some(Block {
+                block_id: 3,
+                block_state: 0,
+            }),
+        );
+
+        let mut notified: Vec<_> = world.pending_updates.iter().copied().collect();
+        notified.sort();
+        let expected: Vec<BlockPos> = vec![
+            [4, 5, 5].into(),
+            [5, 4, 5].into(),
+            [5, 5, 4].into(),
+            [5, 5, 6].into(),
+            [5, 6, 5].into(),
+            [6, 5, 5].into(),
+        ];
+        assert_eq!(notified, expected);
+    }
+
+    #[test]
+    fn sand_falls_when_support_is_removed() {
+        let mut world = world_with(&[([5, 0, 5], 3), ([5, 4, 5], 3), ([5, 5, 5], SAND)]);
+        world.tick();
+
+        world.set_block([5, 4, 5].into(), None);
+        for _ in 0..20 {
+            world.tick();
+        }
+
+        assert_eq!(world.get_block([5, 5, 5].into()), None);
+        assert_eq!(world.get_block([5, 1, 5].into()).unwrap().block_id, SAND);
+        assert!(world.scheduled_ticks().is_empty());
+    }
+
+    #[test]
+    fn ladder_drops_when_its_wall_is_removed() {
+        let mut world = world_with(&[([6, 5, 5], 3), ([5, 5, 5], 15), ([5, 6, 5], 3)]);
+        world.tick();
+        assert_eq!(world.get_block([5, 5, 5].into()).unwrap().block_id, 15);
+
+        // the block above is not what it hangs on
+        world.set_block([5, 6, 5].into(), None);
+        world.tick();
+        assert_eq!(world.get_block([5, 5, 5].into()).unwrap().block_id, 15);
+
+        world.set_block([6, 5, 5].into(), None);
+        world.tick();
+        assert_eq!(world.get_block([5, 5, 5].into()), None);
+    }
+
+    #[test]
+    fn scheduled_tick_waits_for_its_delay() {
+        let mut world = world_with(&[([5, 5, 5], SAND)]);
+        world.pending_updates.clear();
+
+        world.schedule_tick([5, 5, 5].into(), 3);
+        world.schedule_tick([5, 5, 5].into(), 3);
+        assert_eq!(world.scheduled_ticks().len(), 1);
+
+        world.tick();
+        world.tick();
+        assert_eq!(world.get_block([5, 5, 5].into()).unwrap().block_id, SAND);
+        world.tick();
+        assert_eq!(world.get_block([5, 4, 5].into()).unwrap().block_id, SAND);
+    }
+
+    #[test]
+    fn stale_scheduled_tick_is_dropped() {
+        let mut world = world_with(&[([5, 5, 5], SAND)]);
+        world.schedule_tick([5, 5, 5].into(), 1);
+
+        world.set_block(
+            [5, 5, 5].into(),
+            Some(Block {
+                block_id: 3,
+                block_state: 0,
+            }),
+        );
+        world.tick();
+
+        assert_eq!(world.get_block([5, 5, 5].into()).unwrap().block_id, 3);
+        assert_eq!(world.get_block([5, 4, 5].into()), None);
+    }
+
+    #[test]
+    fn block_updates_are_capped_per_tick() {
+        let mut world = world_with(&[]);
+        for _ in 0..MAX_BLOCK_UPDATES_PER_TICK + 10 {
+            world.pending_updates.push_back([5, 5, 5].into());
+        }
+
+        world.tick();
+
+        assert_eq!(world.pending_updates.len(), 10);
+    }
+}
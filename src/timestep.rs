@@ -0,0 +1,67 @@
+use instant::Duration;
+
+/// Length of one simulation tick, physics, world ticks and input all run at this rate.
+pub const TICK_LENGTH: Duration = Duration::from_millis(50);
+pub const TICK_SECONDS: f32 = TICK_LENGTH.as_secs_f32();
+
+/// A long frame (window dragged, debugger attached) only catches up this many ticks, the
+/// rest of the time is dropped instead of freezing the game while it simulates.
+const MAX_TICKS_PER_FRAME: u32 = 10;
+
+/// Turns variable frame times into a whole number of fixed ticks.
+#[derive(Debug, Default)]
+pub struct FixedTimestep {
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the frame time and returns how many ticks to run.
+    pub fn advance(&mut self, frame: Duration) -> u32 {
+        self.accumulator += frame;
+        let mut ticks = 0;
+        while self.accumulator >= TICK_LENGTH {
+            self.accumulator -= TICK_LENGTH;
+            ticks += 1;
+        }
+        if ticks > MAX_TICKS_PER_FRAME {
+            ticks = MAX_TICKS_PER_FRAME;
+        }
+        ticks
+    }
+
+    /// How far the frame is between the last tick and the next one, in `0..1`.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / TICK_SECONDS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_add_up_to_ticks() {
+        let mut timestep = FixedTimestep::new();
+
+        assert_eq!(timestep.advance(Duration::from_millis(30)), 0);
+        assert_eq!(timestep.advance(Duration::from_millis(30)), 1);
+        assert!((timestep.alpha() - 0.2).abs() < 1e-6);
+        assert_eq!(timestep.advance(Duration::from_millis(140)), 3);
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+
+    #[test]
+    fn long_frames_are_capped() {
+        let mut timestep = FixedTimestep::new();
+
+        assert_eq!(
+            timestep.advance(Duration::from_secs(5)),
+            MAX_TICKS_PER_FRAME
+        );
+        assert_eq!(timestep.advance(Duration::ZERO), 0);
+    }
+}